@@ -290,8 +290,13 @@ impl Compiler {
             Group { ref e, i, ref name } => {
                 // it's impossible to have a named capture without an index
                 let i = i.expect("capture index");
-                if i >= self.compiled.captures.len() {
-                    self.compiled.captures.push(name.clone());
+                // Groups are usually seen in order, but not when they've
+                // been renumbered (see `ExecBuilder::outer_groups`).
+                while i >= self.compiled.captures.len() {
+                    self.compiled.captures.push(None);
+                }
+                if self.compiled.captures[i].is_none() {
+                    self.compiled.captures[i] = name.clone();
                     if let Some(ref name) = *name {
                         self.capture_name_idx.insert(name.to_owned(), i);
                     }
@@ -497,6 +502,11 @@ impl Compiler {
         kind: Repeater,
         greedy: bool,
     ) -> Result {
+        // Repeating nothing is still nothing. Compiling it as a loop would
+        // leave a split whose only way forward points back at itself.
+        if let Expr::Empty = *expr {
+            return self.c(expr);
+        }
         match kind {
             Repeater::ZeroOrOne => self.c_repeat_zero_or_one(expr, greedy),
             Repeater::ZeroOrMore => self.c_repeat_zero_or_more(expr, greedy),
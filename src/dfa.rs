@@ -96,6 +96,150 @@ pub fn can_exec(insts: &Program) -> bool {
     true
 }
 
+/// A fully computed DFA transition table that can be executed without this
+/// crate.
+///
+/// The table is computed by eagerly exploring every state reachable from the
+/// start state of the lazy DFA. Its format is:
+///
+/// * `byte_classes` maps each of the 256 possible bytes to its equivalence
+///   class. Bytes in the same class never lead to different states.
+/// * `transitions` has one row per state. Each row has one column per byte
+///   class, followed by one final column for the special end-of-input
+///   symbol. Each entry is the index of the next state.
+/// * `matches` indicates whether each state is a match state.
+///
+/// State `0` is always the dead state (every transition out of it leads back
+/// to itself and it never matches) and state `1` is always the start state.
+///
+/// Matches are delayed by one byte. That is, if feeding the byte at offset `i`
+/// leads to a match state, then a match ends at offset `i`. After the input is
+/// exhausted, the end-of-input column must be followed once; if it leads to a
+/// match state, then a match ends at the end of the input. Searching may stop
+/// as soon as the dead state is entered. The last match seen is the end of
+/// the leftmost-first match. (The table does not report where that match
+/// starts.)
+///
+/// A search using the table looks like this:
+///
+/// ```ignore
+/// let eoi = table.transitions[0].len() - 1;
+/// let (mut s, mut last_match) = (1, None);
+/// for (i, &b) in text.iter().enumerate() {
+///     let cls = table.byte_classes[b as usize] as usize;
+///     s = table.transitions[s][cls] as usize;
+///     if table.matches[s] { last_match = Some(i); }
+///     if s == 0 { break; }
+/// }
+/// if s != 0 && table.matches[table.transitions[s][eoi] as usize] {
+///     last_match = Some(text.len());
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct DfaTable {
+    /// A map from every byte to its equivalence class.
+    pub byte_classes: Vec<u8>,
+    /// The transition table, with one row per state. The last column in each
+    /// row is the transition on the end of input.
+    pub transitions: Vec<Vec<u32>>,
+    /// Whether each state is a match state.
+    pub matches: Vec<bool>,
+}
+
+/// Computes every state of the DFA for the given program and returns its
+/// transition table.
+///
+/// `None` is returned if the program cannot be executed by a DFA, if it has a
+/// Unicode word boundary (which the DFA can only handle on ASCII text) or if
+/// the number of states exceeds the DFA's cache limit.
+pub fn table(prog: &Program) -> Option<DfaTable> {
+    if !can_exec(prog) || prog.has_unicode_word_boundary {
+        return None;
+    }
+    let mut cache = Cache::new(prog);
+    let mut dfa = Fsm {
+        prog: prog,
+        start: 0, // filled in below
+        at: 0,
+        quit_after_match: false,
+        last_match_si: STATE_UNKNOWN,
+        last_cache_flush: 0,
+        cache: &mut cache.inner,
+    };
+    // The table always starts at the beginning of the input. The end of
+    // input is handled by the special EOF column, so it isn't set here.
+    let mut empty_flags = EmptyFlags::default();
+    empty_flags.start = true;
+    empty_flags.start_line = true;
+    dfa.start = match dfa.start_state(
+        &mut cache.qcur,
+        empty_flags,
+        StateFlags::default(),
+    ) {
+        None => return None,
+        Some(si) => si,
+    };
+    // One representative input symbol for each byte class, with EOF last.
+    let mut reps = vec![Byte::eof(); dfa.num_byte_classes()];
+    for b in (0..256).rev() {
+        reps[dfa.u8_class(b as u8)] = Byte::byte(b as u8);
+    }
+    let flush_count = dfa.cache.flush_count;
+    let mut table = DfaTable {
+        byte_classes: prog.byte_classes.clone(),
+        transitions: vec![vec![0; reps.len()]],
+        matches: vec![false],
+    };
+    // The start state is always at index 1, even if it is dead. (Start
+    // states are never match states since matches are delayed by one byte.)
+    table.transitions.push(vec![0; reps.len()]);
+    table.matches.push(false);
+    let mut index: HashMap<StatePtr, u32> = HashMap::new();
+    let mut queue = vec![];
+    if dfa.start != STATE_DEAD {
+        index.insert(dfa.start & STATE_MAX, 1);
+        queue.push(dfa.start & STATE_MAX);
+    }
+    let mut i = 0;
+    while i < queue.len() {
+        let si = queue[i];
+        let row = index[&si] as usize;
+        i += 1;
+        for (cls, &b) in reps.iter().enumerate() {
+            let next = match dfa.next_state(
+                &mut cache.qcur,
+                &mut cache.qnext,
+                si,
+                b,
+            ) {
+                None => return None,
+                Some(next) => next,
+            };
+            // If the cache was wiped, then our state pointers are bunk.
+            if dfa.cache.flush_count != flush_count {
+                return None;
+            }
+            if next == STATE_DEAD {
+                continue;
+            }
+            let next = next & STATE_MAX;
+            let nexti = match index.get(&next) {
+                Some(&nexti) => nexti,
+                None => {
+                    let nexti = usize_to_u32(table.transitions.len());
+                    index.insert(next, nexti);
+                    queue.push(next);
+                    table.transitions.push(vec![0; reps.len()]);
+                    table.matches.push(dfa.state(next).flags.is_match());
+                    nexti
+                }
+            };
+            table.transitions[row][cls] = nexti;
+        }
+    }
+    Some(table)
+}
+
 /// A reusable cache of DFA states.
 ///
 /// This cache is reused between multiple invocations of the same regex
@@ -0,0 +1,211 @@
+// Copyright 2016 The Rust Project Developers. See the COPYRIGHT
+// file at the top-level directory of this distribution and at
+// http://rust-lang.org/COPYRIGHT.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+/*!
+Renders a regex program as an SVG diagram.
+
+Every instruction in the program is drawn as a circle labeled with its index,
+laid out left to right in program order. `Match` instructions are drawn as
+double circles. Instructions that consume input (`Char`, `Ranges` and
+`Bytes`) are drawn with solid arrows labeled with what they match, while
+epsilon transitions (`Save`, `Split` and `EmptyLook`) are drawn with dashed
+arrows. Transitions to the next instruction are straight lines. Forward jumps
+arc above the states and backward jumps arc below them.
+*/
+
+use std::fmt::Write;
+
+use prog::{EmptyLook, Inst, InstPtr, Program};
+
+/// The horizontal distance between the centers of adjacent states.
+const STEP: usize = 90;
+/// The radius of a state's circle.
+const RADIUS: usize = 20;
+/// The horizontal margin to the left of the first state.
+const MARGIN: usize = 60;
+/// The height added to an arc for each state it spans.
+const ARC_STEP: usize = 15;
+
+/// Returns an SVG document that draws the given program.
+pub fn svg(prog: &Program) -> String {
+    let max_span = prog.iter().enumerate().flat_map(|(pc, inst)| {
+        gotos(inst).into_iter().map(move |(goto, _, _)| span(pc, goto))
+    }).max().unwrap_or(0);
+    // The control point of an arc is twice as far from the states as the
+    // top of the arc, so leave enough room for it (and a label).
+    let cy = RADIUS + 2 * arc_height(max_span) + 20;
+    let width = 2 * MARGIN + STEP * prog.len().saturating_sub(1);
+    let height = 2 * cy;
+
+    let mut out = String::new();
+    writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" \
+                   width=\"{}\" height=\"{}\" font-family=\"monospace\" \
+                   font-size=\"12\">", width, height).unwrap();
+    out.push_str(
+        "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"10\" \
+         refY=\"5\" markerWidth=\"6\" markerHeight=\"6\" \
+         orient=\"auto\"><path d=\"M 0 0 L 10 5 L 0 10 z\"/></marker>\
+         </defs>\n");
+
+    if !prog.is_empty() {
+        // An arrow pointing at the start state.
+        let x = center(prog.start);
+        writeln!(out, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" \
+                       stroke=\"black\" marker-end=\"url(#arrow)\"/>",
+                 x - RADIUS - 30, cy, x - RADIUS, cy).unwrap();
+    }
+    for (pc, inst) in prog.iter().enumerate() {
+        for (goto, label, epsilon) in gotos(inst) {
+            edge(&mut out, pc, goto, cy, &label, epsilon);
+        }
+    }
+    for (pc, inst) in prog.iter().enumerate() {
+        let x = center(pc);
+        writeln!(out, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"white\" \
+                       stroke=\"black\"/>", x, cy, RADIUS).unwrap();
+        if let Inst::Match(_) = *inst {
+            writeln!(out, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" \
+                           fill=\"none\" stroke=\"black\"/>",
+                     x, cy, RADIUS - 4).unwrap();
+        }
+        writeln!(out, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" \
+                       dominant-baseline=\"central\">{}</text>",
+                 x, cy, pc).unwrap();
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Draws a single transition from `from` to `to`.
+fn edge(
+    out: &mut String,
+    from: InstPtr,
+    to: InstPtr,
+    cy: usize,
+    label: &str,
+    epsilon: bool,
+) {
+    let dash = if epsilon { " stroke-dasharray=\"4,3\"" } else { "" };
+    let (x1, x2) = (center(from), center(to));
+    let (lx, ly);
+    if to == from + 1 {
+        writeln!(out, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" \
+                       stroke=\"black\"{} marker-end=\"url(#arrow)\"/>",
+                 x1 + RADIUS, cy, x2 - RADIUS, cy, dash).unwrap();
+        lx = (x1 + x2) / 2;
+        ly = cy - 6;
+    } else {
+        // Forward jumps arc above the states, everything else below.
+        let h = arc_height(span(from, to));
+        let (y, cpy) = if to > from {
+            (cy - RADIUS, cy - RADIUS - 2 * h)
+        } else {
+            (cy + RADIUS, cy + RADIUS + 2 * h)
+        };
+        // Self loops would be invisible, so spread their endpoints apart.
+        let (x1, x2) = if to == from {
+            (x1 - RADIUS / 2, x2 + RADIUS / 2)
+        } else {
+            (x1, x2)
+        };
+        writeln!(out, "<path d=\"M {} {} Q {} {} {} {}\" fill=\"none\" \
+                       stroke=\"black\"{} marker-end=\"url(#arrow)\"/>",
+                 x1, y, (x1 + x2) / 2, cpy, x2, y, dash).unwrap();
+        // The midpoint of a quadratic Bézier curve.
+        lx = (x1 + x2) / 2;
+        ly = (y + cpy) / 2 - 4;
+    }
+    if !label.is_empty() {
+        writeln!(out, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}\
+                       </text>", lx, ly, escape(label)).unwrap();
+    }
+}
+
+/// Returns every transition out of the given instruction as a triple of
+/// the target instruction, a label and whether it is an epsilon transition.
+fn gotos(inst: &Inst) -> Vec<(InstPtr, String, bool)> {
+    match *inst {
+        Inst::Match(_) => vec![],
+        Inst::Save(ref inst) => {
+            vec![(inst.goto, format!("save {}", inst.slot), true)]
+        }
+        Inst::Split(ref inst) => vec![
+            (inst.goto1, "".to_owned(), true),
+            (inst.goto2, "".to_owned(), true),
+        ],
+        Inst::EmptyLook(ref inst) => {
+            let label = match inst.look {
+                EmptyLook::StartLine => "(?m:^)",
+                EmptyLook::EndLine => "(?m:$)",
+                EmptyLook::StartText => "^",
+                EmptyLook::EndText => "$",
+                EmptyLook::WordBoundary => r"\b",
+                EmptyLook::NotWordBoundary => r"\B",
+                EmptyLook::WordBoundaryAscii => r"(?-u:\b)",
+                EmptyLook::NotWordBoundaryAscii => r"(?-u:\B)",
+            };
+            vec![(inst.goto, label.to_owned(), true)]
+        }
+        Inst::Char(ref inst) => {
+            vec![(inst.goto, inst.c.escape_default().collect(), false)]
+        }
+        Inst::Ranges(ref inst) => {
+            let mut label = "[".to_owned();
+            for &(s, e) in &inst.ranges {
+                label.extend(s.escape_default());
+                if s != e {
+                    label.push('-');
+                    label.extend(e.escape_default());
+                }
+            }
+            label.push(']');
+            vec![(inst.goto, label, false)]
+        }
+        Inst::Bytes(ref inst) => {
+            let label = if inst.start == inst.end {
+                format!("\\x{:02X}", inst.start)
+            } else {
+                format!("[\\x{:02X}-\\x{:02X}]", inst.start, inst.end)
+            };
+            vec![(inst.goto, label, false)]
+        }
+    }
+}
+
+/// Returns the x coordinate of the center of the given state.
+fn center(pc: InstPtr) -> usize {
+    MARGIN + STEP * pc
+}
+
+/// Returns the number of states between `from` and `to`.
+fn span(from: InstPtr, to: InstPtr) -> usize {
+    if from > to { from - to } else { to - from }
+}
+
+/// Returns how far an arc spanning the given number of states bulges away
+/// from the states.
+fn arc_height(span: usize) -> usize {
+    ARC_STEP * (span + 1)
+}
+
+/// Escapes the given text for use in SVG.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
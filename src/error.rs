@@ -8,6 +8,7 @@
 // option. This file may not be copied, modified, or distributed
 // except according to those terms.
 
+use std::ffi::OsString;
 use std::fmt;
 
 use syntax;
@@ -20,6 +21,9 @@ pub enum Error {
     /// The compiled program exceeded the set size limit.
     /// The argument is the size limit imposed.
     CompiledTooBig(usize),
+    /// The requested operation is not supported for this regular expression.
+    /// The argument describes why.
+    Unsupported(String),
     /// **DEPRECATED:** Will be removed on next major version bump.
     ///
     /// This error is no longer used. (A `RegexSet` can now contain zero or
@@ -39,6 +43,7 @@ impl ::std::error::Error for Error {
         match *self {
             Error::Syntax(ref err) => err.description(),
             Error::CompiledTooBig(_) => "compiled program too big",
+            Error::Unsupported(ref msg) => msg,
             Error::InvalidSet => {
                 "sets must contain 2 or more regular expressions"
             }
@@ -62,6 +67,7 @@ impl fmt::Display for Error {
                 write!(f, "Compiled regex exceeds size limit of {} bytes.",
                        limit)
             }
+            Error::Unsupported(ref msg) => write!(f, "{}", msg),
             Error::InvalidSet => {
                 write!(f, "Sets must contain 2 or more regular expressions.")
             }
@@ -75,3 +81,77 @@ impl From<syntax::Error> for Error {
         Error::Syntax(err)
     }
 }
+
+/// An error that occurred while building a regular expression from an
+/// environment variable.
+#[derive(Debug)]
+pub enum RegexEnvError {
+    /// The environment variable isn't set. The argument is the name of the
+    /// variable.
+    NotPresent(String),
+    /// The value of the environment variable isn't valid UTF-8. The argument
+    /// is the value.
+    NotUtf8(OsString),
+    /// The value of the environment variable isn't a valid regular
+    /// expression.
+    InvalidRegex(Error),
+}
+
+impl ::std::error::Error for RegexEnvError {
+    fn description(&self) -> &str {
+        match *self {
+            RegexEnvError::NotPresent(_) => "environment variable not found",
+            RegexEnvError::NotUtf8(_) => {
+                "environment variable was not valid UTF-8"
+            }
+            RegexEnvError::InvalidRegex(ref err) => err.description(),
+        }
+    }
+
+    fn cause(&self) -> Option<&::std::error::Error> {
+        match *self {
+            RegexEnvError::InvalidRegex(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RegexEnvError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RegexEnvError::NotPresent(ref var) => {
+                write!(f, "Environment variable {} is not set.", var)
+            }
+            RegexEnvError::NotUtf8(ref value) => {
+                write!(f, "Environment variable value {:?} is not valid \
+                           UTF-8.", value)
+            }
+            RegexEnvError::InvalidRegex(ref err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<Error> for RegexEnvError {
+    fn from(err: Error) -> RegexEnvError {
+        RegexEnvError::InvalidRegex(err)
+    }
+}
+
+/// An error that occurred because a search gave up after visiting more
+/// instructions than its step limit allows.
+///
+/// The argument is the step limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StepLimitExceeded(pub u64);
+
+impl ::std::error::Error for StepLimitExceeded {
+    fn description(&self) -> &str {
+        "search exceeded its step limit"
+    }
+}
+
+impl fmt::Display for StepLimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Search exceeded its step limit of {} instructions.", self.0)
+    }
+}
@@ -9,20 +9,21 @@
 // except according to those terms.
 
 use std::cell::RefCell;
+use std::cmp;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use thread_local::CachedThreadLocal;
-use syntax::{Expr, ExprBuilder, Literals};
+use syntax::{Expr, ExprBuilder, Lit, Literals};
 
 use backtrack;
 use compile::Compiler;
 use dfa;
-use error::Error;
+use error::{Error, StepLimitExceeded};
 use input::{ByteInput, CharInput};
 use literals::LiteralSearcher;
 use pikevm;
-use prog::Program;
+use prog::{ComplexityClass, Program};
 use re_bytes;
 use re_trait::{RegularExpression, Slot};
 use re_unicode;
@@ -33,6 +34,7 @@ use set;
 /// In particular, this manages the various compiled forms of a single regular
 /// expression and the choice of which matching engine to use to execute a
 /// regular expression.
+#[derive(Debug)]
 pub struct Exec {
     /// All read only state.
     ro: Arc<ExecReadOnly>,
@@ -43,7 +45,7 @@ pub struct Exec {
 /// ExecNoSync is like Exec, except it embeds a reference to a cache. This
 /// means it is no longer Sync, but we can now avoid the overhead of
 /// synchronization to fetch the cache.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ExecNoSync<'c> {
     /// All read only state.
     ro: &'c Arc<ExecReadOnly>,
@@ -52,6 +54,7 @@ pub struct ExecNoSync<'c> {
 }
 
 /// ExecNoSyncStr is like ExecNoSync, but matches on &str instead of &[u8].
+#[derive(Clone)]
 pub struct ExecNoSyncStr<'c>(ExecNoSync<'c>);
 
 /// ExecReadOnly comprises all read only state for a regex. Namely, all such
@@ -84,6 +87,11 @@ struct ExecReadOnly {
     /// match_type encodes as much upfront knowledge about how we're going to
     /// execute a search as possible.
     match_type: MatchType,
+    /// The options this executor was built with.
+    options: ExecOptions,
+    /// An executor for the same regexes that can search bytes which aren't
+    /// valid UTF-8. It's built the first time it's needed.
+    any_bytes: Mutex<Option<Arc<Exec>>>,
 }
 
 /// Facilitates the construction of an executor by exposing various knobs
@@ -91,10 +99,25 @@ struct ExecReadOnly {
 /// permitted to use.
 pub struct ExecBuilder {
     res: Vec<String>,
+    options: ExecOptions,
+}
+
+/// The knobs of an `ExecBuilder`.
+///
+/// The executor keeps a copy so that regexes derived from it can be compiled
+/// the same way.
+#[derive(Clone, Debug)]
+struct ExecOptions {
     match_type: Option<MatchType>,
     size_limit: usize,
     bytes: bool,
     only_utf8: bool,
+    unicode: Option<bool>,
+    early_literal_match: bool,
+    multi_line: bool,
+    case_insensitive: bool,
+    dot_matches_new_line: bool,
+    outer_groups: Vec<usize>,
 }
 
 impl ExecBuilder {
@@ -116,10 +139,18 @@ impl ExecBuilder {
             where S: AsRef<str>, I: IntoIterator<Item=S> {
         ExecBuilder {
             res: res.into_iter().map(|s| s.as_ref().to_owned()).collect(),
-            match_type: None,
-            size_limit: 10 * (1 << 20),
-            bytes: false,
-            only_utf8: true,
+            options: ExecOptions {
+                match_type: None,
+                size_limit: 10 * (1 << 20),
+                bytes: false,
+                only_utf8: true,
+                unicode: None,
+                early_literal_match: true,
+                multi_line: false,
+                case_insensitive: false,
+                dot_matches_new_line: false,
+                outer_groups: vec![],
+            },
         }
     }
 
@@ -131,7 +162,7 @@ impl ExecBuilder {
     /// This overrides whatever was previously set via the `nfa` or
     /// `bounded_backtracking` methods.
     pub fn automatic(mut self) -> Self {
-        self.match_type = None;
+        self.options.match_type = None;
         self
     }
 
@@ -141,7 +172,7 @@ impl ExecBuilder {
     /// This overrides whatever was previously set via the `automatic` or
     /// `bounded_backtracking` methods.
     pub fn nfa(mut self) -> Self {
-        self.match_type = Some(MatchType::Nfa(MatchNfaType::PikeVM));
+        self.options.match_type = Some(MatchType::Nfa(MatchNfaType::PikeVM));
         self
     }
 
@@ -154,7 +185,8 @@ impl ExecBuilder {
     /// This overrides whatever was previously set via the `automatic` or
     /// `nfa` methods.
     pub fn bounded_backtracking(mut self) -> Self {
-        self.match_type = Some(MatchType::Nfa(MatchNfaType::Backtrack));
+        self.options.match_type =
+            Some(MatchType::Nfa(MatchNfaType::Backtrack));
         self
     }
 
@@ -165,7 +197,7 @@ impl ExecBuilder {
     /// N.B. Typically, multiple programs are compiled for every regular
     /// expression and this limit applies to *each* of them.
     pub fn size_limit(mut self, bytes: usize) -> Self {
-        self.size_limit = bytes;
+        self.options.size_limit = bytes;
         self
     }
 
@@ -179,7 +211,7 @@ impl ExecBuilder {
     /// Note that this does not impact DFA matching engines, which always
     /// execute on bytes.
     pub fn bytes(mut self, yes: bool) -> Self {
-        self.bytes = yes;
+        self.options.bytes = yes;
         self
     }
 
@@ -188,7 +220,76 @@ impl ExecBuilder {
     /// When enabled (the default), all compiled programs exclusively match
     /// valid UTF-8 bytes.
     pub fn only_utf8(mut self, yes: bool) -> Self {
-        self.only_utf8 = yes;
+        self.options.only_utf8 = yes;
+        self
+    }
+
+    /// Sets the default value of the Unicode (`u`) flag for every regex.
+    ///
+    /// When enabled, classes like `\w` and `.` match Unicode scalar values
+    /// instead of bytes. A regex can still turn the flag off with `(?-u)`.
+    ///
+    /// By default, this is enabled exactly when `only_utf8` is. Enabling it
+    /// while `only_utf8` is disabled compiles programs that match Unicode
+    /// classes but may still be run on bytes that aren't valid UTF-8.
+    pub fn unicode(mut self, yes: bool) -> Self {
+        self.options.unicode = Some(yes);
+        self
+    }
+
+    /// Sets the default value of the multi-line (`m`) flag for every regex.
+    ///
+    /// When enabled, `^` and `$` match at the beginning and end of lines
+    /// instead of only at the beginning and end of the text. A regex can
+    /// still turn the flag off with `(?-m)`. It is disabled by default.
+    pub fn multi_line(mut self, yes: bool) -> Self {
+        self.options.multi_line = yes;
+        self
+    }
+
+    /// Sets the default value of the case insensitive (`i`) flag for every
+    /// regex.
+    ///
+    /// A regex can still turn the flag off with `(?-i)`. It is disabled by
+    /// default.
+    pub fn case_insensitive(mut self, yes: bool) -> Self {
+        self.options.case_insensitive = yes;
+        self
+    }
+
+    /// Sets the default value of the any character (`s`) flag for every
+    /// regex.
+    ///
+    /// When enabled, `.` matches `\n` too. A regex can still turn the flag
+    /// off with `(?-s)`. It is disabled by default.
+    pub fn dot_matches_new_line(mut self, yes: bool) -> Self {
+        self.options.dot_matches_new_line = yes;
+        self
+    }
+
+    /// Gives the first capture groups of the regex the indices in `indices`,
+    /// in order. The rest of the groups take the remaining indices, in order.
+    ///
+    /// Capture groups are normally numbered by the position of their opening
+    /// parenthesis. This lets a group that is wrapped around a regex take
+    /// the next free index instead, so the groups inside keep theirs. By
+    /// default, every group is numbered by its position.
+    pub fn outer_groups(mut self, indices: Vec<usize>) -> Self {
+        self.options.outer_groups = indices;
+        self
+    }
+
+    /// When enabled (the default), regexes that are equivalent to a literal
+    /// search are matched by searching for the literals directly, without
+    /// running any regex engine.
+    ///
+    /// Disabling this forces a regex engine to run even for pure literals,
+    /// which is mostly useful for testing.
+    ///
+    /// This has no effect if the matching engine was set via the `nfa` or
+    /// `bounded_backtracking` methods.
+    pub fn early_literal_match(mut self, yes: bool) -> Self {
+        self.options.early_literal_match = yes;
         self
     }
 
@@ -202,31 +303,60 @@ impl ExecBuilder {
                 dfa_reverse: Program::new(),
                 suffixes: LiteralSearcher::empty(),
                 match_type: MatchType::Nothing,
+                options: self.options,
+                any_bytes: Mutex::new(None),
             });
             return Ok(Exec { ro: ro, cache: CachedThreadLocal::new() });
         }
-        let parsed = try!(Parsed::parse(&self.res, self.only_utf8));
+        let unicode = self.options.unicode.unwrap_or(self.options.only_utf8);
+        let parser = ExprBuilder::new()
+            .allow_bytes(!self.options.only_utf8)
+            .unicode(unicode)
+            .multi_line(self.options.multi_line)
+            .case_insensitive(self.options.case_insensitive)
+            .dot_matches_new_line(self.options.dot_matches_new_line);
+        let mut parsed = try!(Parsed::parse(&self.res, parser));
+        if !self.options.outer_groups.is_empty() {
+            for expr in &mut parsed.exprs {
+                renumber_outer_groups(expr, &self.options.outer_groups);
+            }
+        }
         let mut nfa = try!(
             Compiler::new()
-                     .size_limit(self.size_limit)
-                     .bytes(self.bytes)
-                     .only_utf8(self.only_utf8)
+                     .size_limit(self.options.size_limit)
+                     .bytes(self.options.bytes)
+                     .only_utf8(self.options.only_utf8)
                      .compile(&parsed.exprs));
         let mut dfa = try!(
             Compiler::new()
-                     .size_limit(self.size_limit)
+                     .size_limit(self.options.size_limit)
                      .dfa(true)
-                     .only_utf8(self.only_utf8)
+                     .only_utf8(self.options.only_utf8)
                      .compile(&parsed.exprs));
         let dfa_reverse = try!(
             Compiler::new()
-                     .size_limit(self.size_limit)
+                     .size_limit(self.options.size_limit)
                      .dfa(true)
-                     .only_utf8(self.only_utf8)
+                     .only_utf8(self.options.only_utf8)
                      .reverse(true)
                      .compile(&parsed.exprs));
 
-        let prefixes = parsed.prefixes.unambiguous_prefixes();
+        let mut prefixes = parsed.prefixes.unambiguous_prefixes();
+        if prefixes.literals().is_empty() {
+            // Even without a literal prefix, the bytes that a match can start
+            // with might let us skip most of the text.
+            if let Some(bytes) = nfa.first_bytes() {
+                let mut lits = Literals::empty();
+                let all_added = bytes.into_iter().all(|b| {
+                    let mut lit = Lit::new(vec![b]);
+                    lit.cut();
+                    lits.add(lit)
+                });
+                if all_added {
+                    prefixes = lits;
+                }
+            }
+        }
         let suffixes = parsed.suffixes.unambiguous_suffixes();
         nfa.prefixes = LiteralSearcher::prefixes(prefixes);
         dfa.prefixes = nfa.prefixes.clone();
@@ -238,8 +368,11 @@ impl ExecBuilder {
             dfa_reverse: dfa_reverse,
             suffixes: LiteralSearcher::suffixes(suffixes),
             match_type: MatchType::Nothing,
+            options: self.options,
+            any_bytes: Mutex::new(None),
         };
-        ro.match_type = ro.choose_match_type(self.match_type);
+        ro.match_type = ro.choose_match_type(
+            ro.options.match_type, ro.options.early_literal_match);
         // println!("MATCH TYPE for '{:?}': {:?}", ro.res, ro.match_type);
 
         let ro = Arc::new(ro);
@@ -645,11 +778,18 @@ impl<'c> ExecNoSync<'c> {
                     .map(|(s, e)| (start + s, start + e))
             }
             AnchoredStart => {
+                // The regex can only match at the very beginning of the
+                // text, which is behind us when starting anywhere else.
+                if start > 0 {
+                    return None;
+                }
                 let lits = &self.ro.nfa.prefixes;
-                lits.find_start(&text[start..])
+                lits.find_start(text)
+            }
+            AnchoredEnd => {
+                self.ro.suffixes.find_end(&text[start..])
                     .map(|(s, e)| (start + s, start + e))
             }
-            AnchoredEnd => self.ro.suffixes.find_end(&text),
         }
     }
 
@@ -797,6 +937,47 @@ impl Exec {
         re_bytes::RegexSet::from(self)
     }
 
+    /// Returns an executor for the same regexes as this one that searches
+    /// bytes which aren't necessarily valid UTF-8.
+    ///
+    /// Unlike compiling the regexes with `only_utf8` disabled, classes like
+    /// `\w` keep the meaning they have in this executor. The executor is
+    /// compiled the first time it's asked for and reused afterwards.
+    pub fn any_bytes(&self) -> Result<Arc<Exec>, Error> {
+        let mut any_bytes = self.ro.any_bytes.lock().unwrap();
+        if let Some(ref exec) = *any_bytes {
+            return Ok(exec.clone());
+        }
+        let options = &self.ro.options;
+        let builder = ExecBuilder {
+            res: self.ro.res.clone(),
+            options: options.clone(),
+        };
+        let exec = Arc::new(try!(builder
+            .bytes(true)
+            .only_utf8(false)
+            .unicode(options.unicode.unwrap_or(options.only_utf8))
+            .build()));
+        *any_bytes = Some(exec.clone());
+        Ok(exec)
+    }
+
+    /// Returns the indices given to the first capture groups of the regex.
+    ///
+    /// See `ExecBuilder::outer_groups`.
+    pub fn outer_groups(&self) -> &[usize] {
+        &self.ro.options.outer_groups
+    }
+
+    /// Returns a builder for `re` that starts out with the same options that
+    /// this executor was built with.
+    pub fn builder_for(&self, re: &str) -> ExecBuilder {
+        ExecBuilder {
+            res: vec![re.to_owned()],
+            options: self.ro.options.clone(),
+        }
+    }
+
     /// The original regular expressions given by the caller that were
     /// compiled.
     pub fn regex_strings(&self) -> &[String] {
@@ -815,6 +996,173 @@ impl Exec {
     pub fn capture_name_idx(&self) -> &Arc<HashMap<String, usize>> {
         &self.ro.nfa.capture_name_idx
     }
+
+    /// Returns the worst case time complexity of a search.
+    pub fn complexity_class(&self) -> ComplexityClass {
+        self.ro.nfa.complexity_class()
+    }
+
+    /// Returns true if the regex can be executed with a single NFA thread.
+    pub fn is_deterministic(&self) -> bool {
+        self.ro.nfa.is_deterministic()
+    }
+
+    /// Run the Pike VM on `text` and return the largest number of threads
+    /// it had at any one position.
+    pub fn max_thread_count(&self, text: &[u8]) -> usize {
+        self.pikevm_stats(text).max_threads
+    }
+
+    /// Run the Pike VM on `text` and return the number of instructions it
+    /// executed along with the number of bytes it looked at.
+    ///
+    /// The number of instructions is always `0` unless the `instrument`
+    /// feature is enabled.
+    pub fn instruction_count(&self, text: &[u8]) -> (u64, usize) {
+        let stats = self.pikevm_stats(text);
+        (stats.insts, stats.bytes)
+    }
+
+    /// Return the start of the leftmost match of every regex in this
+    /// executor, using a single pass of the Pike VM over `text`.
+    pub fn first_match_starts(&self, text: &[u8]) -> Vec<Option<usize>> {
+        if self.ro.nfa.insts.is_empty() {
+            return vec![None; self.ro.res.len()];
+        }
+        let cache = self.searcher().cache;
+        if self.ro.nfa.uses_bytes() {
+            pikevm::Fsm::first_match_starts(
+                &self.ro.nfa, cache, ByteInput::new(text), 0)
+        } else {
+            pikevm::Fsm::first_match_starts(
+                &self.ro.nfa, cache, CharInput::new(text), 0)
+        }
+    }
+
+    /// Return the leftmost-first match of every regex in this executor that
+    /// starts at each position in `text`, as `(regex index, start, end)`.
+    ///
+    /// Matches are grouped by regex and then ordered by where they start.
+    /// Every match takes its own Pike VM search, so this may take time
+    /// quadratic in the length of `text`.
+    pub fn overlapping_matches(
+        &self,
+        text: &[u8],
+    ) -> Vec<(usize, usize, usize)> {
+        let mut found = vec![];
+        if self.ro.nfa.insts.is_empty() {
+            return found;
+        }
+        let cache = self.searcher().cache;
+        for pattern in 0..self.ro.res.len() {
+            let mut start = 0;
+            while start <= text.len() {
+                let m = if self.ro.nfa.uses_bytes() {
+                    pikevm::Fsm::find_pattern(
+                        &self.ro.nfa, cache, ByteInput::new(text), start,
+                        pattern)
+                } else {
+                    pikevm::Fsm::find_pattern(
+                        &self.ro.nfa, cache, CharInput::new(text), start,
+                        pattern)
+                };
+                let (s, e) = match m {
+                    None => break,
+                    Some(m) => m,
+                };
+                found.push((pattern, s, e));
+                // The next match must start later. When the text is known
+                // to be UTF-8, skip to the start of the next character.
+                start = s + 1;
+                if self.ro.nfa.only_utf8() {
+                    while start < text.len() && text[start] & 0xC0 == 0x80 {
+                        start += 1;
+                    }
+                }
+            }
+        }
+        found
+    }
+
+    /// Run the Pike VM on `text` to find the leftmost-first match that
+    /// begins exactly at `start`.
+    pub fn find_anchored_at(
+        &self,
+        text: &[u8],
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        if self.ro.nfa.insts.is_empty() {
+            return None;
+        }
+        let cache = self.searcher().cache;
+        let mut slots = [None, None];
+        let matched = if self.ro.nfa.uses_bytes() {
+            pikevm::Fsm::exec_anchored(
+                &self.ro.nfa, cache, &mut slots, ByteInput::new(text), start)
+        } else {
+            pikevm::Fsm::exec_anchored(
+                &self.ro.nfa, cache, &mut slots, CharInput::new(text), start)
+        };
+        match (matched, slots[0], slots[1]) {
+            (true, Some(s), Some(e)) => Some((s, e)),
+            _ => None,
+        }
+    }
+
+    /// Run the Pike VM on `text` to find the leftmost-first match, giving up
+    /// once more than `limit` instructions have been visited.
+    pub fn find_limited(
+        &self,
+        text: &[u8],
+        limit: u64,
+    ) -> Result<Option<(usize, usize)>, StepLimitExceeded> {
+        let cache = self.searcher().cache;
+        let mut slots = [None, None];
+        let matched = if self.ro.nfa.uses_bytes() {
+            pikevm::Fsm::exec_limited(
+                &self.ro.nfa, cache, &mut [false], &mut slots, false,
+                ByteInput::new(text), 0, limit)
+        } else {
+            pikevm::Fsm::exec_limited(
+                &self.ro.nfa, cache, &mut [false], &mut slots, false,
+                CharInput::new(text), 0, limit)
+        };
+        match (matched, slots[0], slots[1]) {
+            (None, _, _) => Err(StepLimitExceeded(limit)),
+            (Some(true), Some(s), Some(e)) => Ok(Some((s, e))),
+            (Some(_), _, _) => Ok(None),
+        }
+    }
+
+    /// Run the Pike VM on `text` to find the leftmost-first match and return
+    /// statistics about its execution.
+    fn pikevm_stats(&self, text: &[u8]) -> pikevm::Stats {
+        let cache = self.searcher().cache;
+        if self.ro.nfa.uses_bytes() {
+            pikevm::Fsm::exec_stats(
+                &self.ro.nfa, cache, ByteInput::new(text), 0)
+        } else {
+            pikevm::Fsm::exec_stats(
+                &self.ro.nfa, cache, CharInput::new(text), 0)
+        }
+    }
+
+    /// Describe every instruction of the NFA program in English.
+    pub fn explain(&self) -> String {
+        self.ro.nfa.explain()
+    }
+
+    /// Render the NFA program as an SVG diagram.
+    #[cfg(feature = "diagram")]
+    pub fn diagram_svg(&self) -> String {
+        ::diagram::svg(&self.ro.nfa)
+    }
+
+    /// Compute the complete transition table of the forward DFA.
+    pub fn dfa_table(&self) -> Result<dfa::DfaTable, Error> {
+        dfa::table(&self.ro.dfa).ok_or_else(|| Error::Unsupported(
+            "regex cannot be compiled to a DFA table".to_owned()))
+    }
 }
 
 impl Clone for Exec {
@@ -827,7 +1175,11 @@ impl Clone for Exec {
 }
 
 impl ExecReadOnly {
-    fn choose_match_type(&self, hint: Option<MatchType>) -> MatchType {
+    fn choose_match_type(
+        &self,
+        hint: Option<MatchType>,
+        early_literal_match: bool,
+    ) -> MatchType {
         use self::MatchType::*;
         if let Some(Nfa(_)) = hint {
             return hint.unwrap();
@@ -839,7 +1191,7 @@ impl ExecReadOnly {
         // If our set of prefixes is complete, then we can use it to find
         // a match in lieu of a regex engine. This doesn't quit work well in
         // the presence of multiple regexes, so only do it when there's one.
-        if self.res.len() == 1 {
+        if self.res.len() == 1 && early_literal_match {
             if self.nfa.prefixes.complete() {
                 return if self.nfa.is_anchored_start {
                     Literal(MatchLiteralType::AnchoredStart)
@@ -943,6 +1295,40 @@ impl ProgramCacheInner {
     }
 }
 
+/// Renumbers the capture groups of `expr` so that its first groups get the
+/// indices in `outer` and the rest get the remaining indices, in order.
+fn renumber_outer_groups(expr: &mut Expr, outer: &[usize]) {
+    let mut count = 0;
+    visit_groups(expr, &mut |i| count = cmp::max(count, *i));
+    if outer.len() > count || outer.iter().any(|&i| i == 0 || i > count) {
+        // The indices don't fit this regex, so leave it alone.
+        return;
+    }
+    let mut indices = vec![0];
+    indices.extend(outer.iter().cloned());
+    indices.extend((1..count + 1).filter(|i| !outer.contains(i)));
+    visit_groups(expr, &mut |i| *i = indices[*i]);
+}
+
+/// Calls `f` with the index of every capture group in `expr`.
+fn visit_groups<F: FnMut(&mut usize)>(expr: &mut Expr, f: &mut F) {
+    match *expr {
+        Expr::Group { ref mut e, ref mut i, .. } => {
+            if let Some(ref mut i) = *i {
+                f(i);
+            }
+            visit_groups(e, f);
+        }
+        Expr::Repeat { ref mut e, .. } => visit_groups(e, f),
+        Expr::Concat(ref mut es) | Expr::Alternate(ref mut es) => {
+            for e in es {
+                visit_groups(e, f);
+            }
+        }
+        _ => {}
+    }
+}
+
 /// An intermediate data structure for parsing a bunch of expressions and
 /// correctly extracting the prefixes and suffixes of all expressions.
 struct Parsed {
@@ -952,16 +1338,12 @@ struct Parsed {
 }
 
 impl Parsed {
-    fn parse(res: &[String], only_utf8: bool) -> Result<Parsed, Error> {
+    fn parse(res: &[String], parser: ExprBuilder) -> Result<Parsed, Error> {
         let mut exprs = Vec::with_capacity(res.len());
         let mut prefixes = Some(Literals::empty());
         let mut suffixes = Some(Literals::empty());
         for re in res {
-            let parser =
-                ExprBuilder::new()
-                    .allow_bytes(!only_utf8)
-                    .unicode(only_utf8);
-            let expr = try!(parser.parse(re));
+            let expr = try!(parser.clone().parse(re));
             prefixes = prefixes.and_then(|mut prefixes| {
                 if !prefixes.union_prefixes(&expr) {
                     None
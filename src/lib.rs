@@ -505,19 +505,28 @@
 
 extern crate aho_corasick;
 extern crate memchr;
+#[cfg(feature = "rayon")] extern crate rayon;
 extern crate thread_local;
 #[cfg(test)] extern crate quickcheck;
 extern crate regex_syntax as syntax;
 extern crate utf8_ranges;
 
-pub use error::Error;
-pub use set::{RegexSet, SetMatches, SetMatchesIntoIter, SetMatchesIter};
+pub use dfa::DfaTable;
+pub use error::{Error, RegexEnvError, StepLimitExceeded};
+pub use prog::ComplexityClass;
+pub use set::{
+    RegexSet, RegexSetBuilder, SetMatches, SetMatchesIntoIter, SetMatchesIter,
+};
 pub use re_unicode::{
     Regex, Captures, SubCaptures, SubCapturesPos, SubCapturesNamed,
-    CaptureNames, FindCaptures, FindMatches,
-    Replacer, NoExpand, RegexSplits, RegexSplitsN,
-    quote, is_match,
+    CaptureNames, FindCaptures, FindMatches, GrepCaptures, GrepFileItem,
+    GrepFiles, GrepInverseLines, GrepLines, GrepMatches, GroupNames,
+    MatchPositions, MatchWindows, MmapMatches,
+    Replacer, NoExpand, RegexSplits, RegexSplitsN, TextSegment, TextSegments,
+    Token, Tokens, quote, is_match,
 };
+#[cfg(feature = "rayon")]
+pub use re_unicode::ParallelGroups;
 
 /**
 Match regular expressions on arbitrary bytes.
@@ -613,6 +622,8 @@ mod backtrack;
 mod utf8;
 mod compile;
 mod dfa;
+#[cfg(feature = "diagram")]
+mod diagram;
 mod error;
 mod exec;
 mod expand;
@@ -25,6 +25,7 @@
 // Therefore, the Pike VM is generally treated as the fallback when the other
 // matching engines either aren't feasible to run or are insufficient.
 
+use std::cmp;
 use std::mem;
 
 use exec::ProgramCache;
@@ -34,8 +35,11 @@ use re_trait::Slot;
 use sparse::SparseSet;
 
 /// An NFA simulation matching engine.
+///
+/// `M` picks the kind of search to run. Normal searches use `Plain`, which
+/// adds no work to the matching loop.
 #[derive(Debug)]
-pub struct Fsm<'r, I> {
+pub struct Fsm<'r, I, M> {
     /// The sequence of opcodes (among other things) that is actually executed.
     ///
     /// The program may be byte oriented or Unicode codepoint oriented.
@@ -45,6 +49,140 @@ pub struct Fsm<'r, I> {
     stack: &'r mut Vec<FollowEpsilon>,
     /// The input to search.
     input: I,
+    /// The kind of search being run.
+    mode: M,
+}
+
+/// A kind of search the NFA can run.
+///
+/// Every method is called from the matching loop. The defaults describe a
+/// normal search, so that a mode which doesn't override them costs nothing
+/// once it's been monomorphized.
+pub trait Mode {
+    /// Whether threads may only start at the position where the search
+    /// began, as if the program were anchored there.
+    fn anchored(&self) -> bool { false }
+
+    /// Whether every thread records where it started in its first capture
+    /// slot.
+    fn records_starts(&self) -> bool { false }
+
+    /// Whether only the regex with this match slot counts, in which case the
+    /// search stops at its leftmost-first match.
+    fn pattern(&self) -> Option<usize> { None }
+
+    /// Called for every instruction visited.
+    fn count_inst(&mut self) {}
+
+    /// Called at every position in the input that is looked at, with the
+    /// number of threads alive there and the number of bytes looked at so
+    /// far.
+    fn observe(&mut self, _nthreads: usize, _bytes: usize) {}
+
+    /// Whether the search should give up before looking at the next
+    /// position in the input.
+    fn give_up(&self) -> bool { false }
+
+    /// Called when a thread reaches the `Match` instruction for the regex
+    /// with the given match slot, with the captures of that thread.
+    fn on_match(&mut self, _match_slot: usize, _thread_caps: &[Slot]) {}
+}
+
+/// A normal search.
+#[derive(Debug)]
+pub struct Plain;
+
+impl Mode for Plain {}
+
+/// A search that records, for every regex in the program, the earliest
+/// start of a match. It's indexed by match slot.
+#[derive(Debug)]
+pub struct FirstStarts(Vec<Option<usize>>);
+
+impl Mode for FirstStarts {
+    fn records_starts(&self) -> bool { true }
+
+    fn on_match(&mut self, match_slot: usize, thread_caps: &[Slot]) {
+        // Threads that started earlier take priority, but a later match can
+        // still have started earlier.
+        if let Some(start) = thread_caps[0] {
+            let earlier = match self.0[match_slot] {
+                None => true,
+                Some(prev) => start < prev,
+            };
+            if earlier {
+                self.0[match_slot] = Some(start);
+            }
+        }
+    }
+}
+
+/// A search for matches that begin exactly where the search began.
+#[derive(Debug)]
+pub struct Anchored;
+
+impl Mode for Anchored {
+    fn anchored(&self) -> bool { true }
+}
+
+/// A search for the leftmost-first match of just the regex with this match
+/// slot. Match positions are taken from where each thread started.
+#[derive(Debug)]
+pub struct OnePattern(usize);
+
+impl Mode for OnePattern {
+    fn records_starts(&self) -> bool { true }
+
+    fn pattern(&self) -> Option<usize> { Some(self.0) }
+}
+
+/// A search that gives up after visiting a number of instructions.
+#[derive(Debug)]
+pub struct Limited {
+    /// The number of instructions that may still be visited.
+    steps_left: u64,
+    /// Whether the search ran out of steps.
+    out_of_steps: bool,
+}
+
+impl Mode for Limited {
+    fn count_inst(&mut self) {
+        if self.steps_left == 0 {
+            self.out_of_steps = true;
+        } else {
+            self.steps_left -= 1;
+        }
+    }
+
+    fn give_up(&self) -> bool { self.out_of_steps }
+}
+
+/// Statistics gathered while executing the NFA.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stats {
+    /// The largest number of threads alive at any one position in the input.
+    pub max_threads: usize,
+    /// The number of instructions executed. This is always `0` unless the
+    /// `instrument` feature is enabled.
+    pub insts: u64,
+    /// The number of bytes of input that were looked at.
+    pub bytes: usize,
+}
+
+impl Mode for Stats {
+    fn count_inst(&mut self) {
+        #[cfg(feature = "instrument")]
+        {
+            self.insts += 1;
+        }
+    }
+
+    fn observe(&mut self, nthreads: usize, bytes: usize) {
+        if nthreads > self.max_threads {
+            self.max_threads = nthreads;
+        }
+        self.bytes = bytes;
+    }
 }
 
 /// A cached allocation that can be reused on each execution.
@@ -94,7 +232,7 @@ impl Cache {
     }
 }
 
-impl<'r, I: Input> Fsm<'r, I> {
+impl<'r, I: Input> Fsm<'r, I, Plain> {
     /// Execute the NFA matching engine.
     ///
     /// If there's a match, `exec` returns `true` and populates the given
@@ -110,13 +248,14 @@ impl<'r, I: Input> Fsm<'r, I> {
     ) -> bool {
         let mut cache = cache.borrow_mut();
         let mut cache = &mut cache.pikevm;
-        cache.clist.resize(prog.len(), prog.captures.len());
-        cache.nlist.resize(prog.len(), prog.captures.len());
+        cache.clist.resize(prog.len(), slots.len());
+        cache.nlist.resize(prog.len(), slots.len());
         let at = input.at(start);
         Fsm {
             prog: prog,
             stack: &mut cache.stack,
             input: input,
+            mode: Plain,
         }.exec_(
             &mut cache.clist,
             &mut cache.nlist,
@@ -127,6 +266,185 @@ impl<'r, I: Input> Fsm<'r, I> {
         )
     }
 
+    /// Execute the NFA matching engine to find the leftmost-first match
+    /// without captures, and return statistics about the execution.
+    pub fn exec_stats(
+        prog: &'r Program,
+        cache: &ProgramCache,
+        input: I,
+        start: usize,
+    ) -> Stats {
+        let mut cache = cache.borrow_mut();
+        let cache = &mut cache.pikevm;
+        cache.clist.resize(prog.len(), 0);
+        cache.nlist.resize(prog.len(), 0);
+        let at = input.at(start);
+        let mut fsm = Fsm {
+            prog: prog,
+            stack: &mut cache.stack,
+            input: input,
+            mode: Stats::default(),
+        };
+        fsm.exec_(
+            &mut cache.clist,
+            &mut cache.nlist,
+            &mut vec![false; prog.matches.len()],
+            &mut [],
+            false,
+            at,
+        );
+        fsm.mode
+    }
+
+    /// Execute the NFA matching engine and return, for every regex in the
+    /// program, the start of its leftmost match.
+    ///
+    /// Every thread remembers where it started in its first capture slot, so
+    /// this works for programs without `Save` instructions (like the ones
+    /// compiled for regex sets).
+    pub fn first_match_starts(
+        prog: &'r Program,
+        cache: &ProgramCache,
+        input: I,
+        start: usize,
+    ) -> Vec<Option<usize>> {
+        let mut cache = cache.borrow_mut();
+        let cache = &mut cache.pikevm;
+        let nslots = 2 * cmp::max(prog.captures.len(), 1);
+        cache.clist.resize(prog.len(), nslots);
+        cache.nlist.resize(prog.len(), nslots);
+        let at = input.at(start);
+        let mut fsm = Fsm {
+            prog: prog,
+            stack: &mut cache.stack,
+            input: input,
+            mode: FirstStarts(vec![None; prog.matches.len()]),
+        };
+        fsm.exec_(
+            &mut cache.clist,
+            &mut cache.nlist,
+            &mut vec![false; prog.matches.len()],
+            &mut vec![None; nslots],
+            false,
+            at,
+        );
+        fsm.mode.0
+    }
+
+    /// Execute the NFA matching engine, only looking for matches that begin
+    /// exactly at `start`.
+    ///
+    /// This behaves as if the program started with `^`, where `^` matches
+    /// at `start`, but look-around assertions still see the whole input.
+    pub fn exec_anchored(
+        prog: &'r Program,
+        cache: &ProgramCache,
+        slots: &mut [Slot],
+        input: I,
+        start: usize,
+    ) -> bool {
+        let mut cache = cache.borrow_mut();
+        let cache = &mut cache.pikevm;
+        cache.clist.resize(prog.len(), slots.len());
+        cache.nlist.resize(prog.len(), slots.len());
+        let at = input.at(start);
+        Fsm {
+            prog: prog,
+            stack: &mut cache.stack,
+            input: input,
+            mode: Anchored,
+        }.exec_(
+            &mut cache.clist,
+            &mut cache.nlist,
+            &mut [false],
+            slots,
+            false,
+            at,
+        )
+    }
+
+    /// Execute the NFA matching engine to find the leftmost-first match of
+    /// just one of the regexes in the program.
+    ///
+    /// Like `first_match_starts`, this works for programs without `Save`
+    /// instructions.
+    pub fn find_pattern(
+        prog: &'r Program,
+        cache: &ProgramCache,
+        input: I,
+        start: usize,
+        pattern: usize,
+    ) -> Option<(usize, usize)> {
+        let mut cache = cache.borrow_mut();
+        let cache = &mut cache.pikevm;
+        cache.clist.resize(prog.len(), 2);
+        cache.nlist.resize(prog.len(), 2);
+        let at = input.at(start);
+        let mut slots = [None, None];
+        let matched = Fsm {
+            prog: prog,
+            stack: &mut cache.stack,
+            input: input,
+            mode: OnePattern(pattern),
+        }.exec_(
+            &mut cache.clist,
+            &mut cache.nlist,
+            &mut [false],
+            &mut slots,
+            false,
+            at,
+        );
+        match (matched, slots[0], slots[1]) {
+            (true, Some(s), Some(e)) => Some((s, e)),
+            _ => None,
+        }
+    }
+
+    /// Execute the NFA matching engine, visiting at most `limit`
+    /// instructions.
+    ///
+    /// This is like `exec`, except `None` is returned if the search runs out
+    /// of steps before it's done. The limit is only checked between positions
+    /// in the input, so up to one position's worth of extra instructions may
+    /// be visited.
+    pub fn exec_limited(
+        prog: &'r Program,
+        cache: &ProgramCache,
+        matches: &mut [bool],
+        slots: &mut [Slot],
+        quit_after_match: bool,
+        input: I,
+        start: usize,
+        limit: u64,
+    ) -> Option<bool> {
+        let mut cache = cache.borrow_mut();
+        let cache = &mut cache.pikevm;
+        cache.clist.resize(prog.len(), slots.len());
+        cache.nlist.resize(prog.len(), slots.len());
+        let at = input.at(start);
+        let mut fsm = Fsm {
+            prog: prog,
+            stack: &mut cache.stack,
+            input: input,
+            mode: Limited { steps_left: limit, out_of_steps: false },
+        };
+        let matched = fsm.exec_(
+            &mut cache.clist,
+            &mut cache.nlist,
+            matches,
+            slots,
+            quit_after_match,
+            at,
+        );
+        if fsm.mode.out_of_steps {
+            None
+        } else {
+            Some(matched)
+        }
+    }
+}
+
+impl<'r, I: Input, M: Mode> Fsm<'r, I, M> {
     fn exec_(
         &mut self,
         mut clist: &mut Threads,
@@ -136,11 +454,15 @@ impl<'r, I: Input> Fsm<'r, I> {
         quit_after_match: bool,
         mut at: InputAt,
     ) -> bool {
+        let start = at.pos();
         let mut matched = false;
         let mut all_matched = false;
         clist.set.clear();
         nlist.set.clear();
 'LOOP:  loop {
+            if self.mode.give_up() {
+                break;
+            }
             if clist.set.is_empty() {
                 // Three ways to bail out when our current set of threads is
                 // empty.
@@ -150,18 +472,20 @@ impl<'r, I: Input> Fsm<'r, I> {
                 //    looking for matches for multiple regexes, unless we know
                 //    they all matched.)
                 //
-                // 2. If the expression starts with a '^' we can terminate as
-                //    soon as the last thread dies.
+                // 2. If the expression starts with a '^' (or the search is
+                //    anchored) we can terminate as soon as the last thread
+                //    dies.
                 if (matched && matches.len() <= 1)
                     || all_matched
-                    || (!at.is_start() && self.prog.is_anchored_start) {
+                    || (!at.is_start() && self.prog.is_anchored_start)
+                    || (self.mode.anchored() && at.pos() != start) {
                     break;
                 }
 
                 // 3. If there's a literal prefix for the program, try to
                 //    jump ahead quickly. If it can't be found, then we can
                 //    bail out early.
-                if !self.prog.prefixes.is_empty() {
+                if !self.mode.anchored() && !self.prog.prefixes.is_empty() {
                     at = match self.input.prefix_at(&self.prog.prefixes, at) {
                         None => break,
                         Some(at) => at,
@@ -173,9 +497,15 @@ impl<'r, I: Input> Fsm<'r, I> {
             // a state starting at the current position in the input for the
             // beginning of the program only if we don't already have a match.
             if clist.set.is_empty()
-                || (!self.prog.is_anchored_start && !all_matched) {
+                || (!self.prog.is_anchored_start
+                    && !self.mode.anchored()
+                    && !all_matched) {
+                if self.mode.records_starts() {
+                    slots[0] = Some(at.pos());
+                }
                 self.add(&mut clist, slots, 0, at);
             }
+            self.mode.observe(clist.set.len(), at.next_pos() - start);
             // The previous call to "add" actually inspects the position just
             // before the current character. For stepping through the machine,
             // we can to look at the current character, so we advance the
@@ -199,7 +529,8 @@ impl<'r, I: Input> Fsm<'r, I> {
                         // position), then we can quit right now.
                         break 'LOOP;
                     }
-                    if self.prog.matches.len() == 1 {
+                    if self.prog.matches.len() == 1
+                        || self.mode.pattern().is_some() {
                         // We don't need to check the rest of the threads
                         // in this set because we've matched something
                         // ("leftmost-first"). However, we still need to check
@@ -207,7 +538,8 @@ impl<'r, I: Input> Fsm<'r, I> {
                         // greedy matching.
                         //
                         // This is only true on normal regexes. For regex sets,
-                        // we need to mush on to observe other matches.
+                        // we need to mush on to observe other matches, unless
+                        // we're only looking for one of them.
                         break;
                     }
                 }
@@ -245,11 +577,24 @@ impl<'r, I: Input> Fsm<'r, I> {
         at_next: InputAt,
     ) -> bool {
         use prog::Inst::*;
+        self.mode.count_inst();
         match self.prog[ip] {
             Match(match_slot) => {
+                if let Some(pattern) = self.mode.pattern() {
+                    if match_slot != pattern {
+                        return false;
+                    }
+                    // There's only one regex we care about, so once it
+                    // has matched, no later start can beat this one.
+                    matches[0] = true;
+                    slots[0] = thread_caps[0];
+                    slots[1] = Some(at.pos());
+                    return true;
+                }
                 if match_slot < matches.len() {
                     matches[match_slot] = true;
                 }
+                self.mode.on_match(match_slot, thread_caps);
                 for (slot, val) in slots.iter_mut().zip(thread_caps.iter()) {
                     *slot = *val;
                 }
@@ -320,6 +665,7 @@ impl<'r, I: Input> Fsm<'r, I> {
                 return;
             }
             nlist.set.add(ip);
+            self.mode.count_inst();
             match self.prog[ip] {
                 EmptyLook(ref inst) => {
                     let prev = self.input.previous_char(at);
@@ -363,13 +709,21 @@ impl Threads {
         }
     }
 
-    fn resize(&mut self, num_insts: usize, ncaps: usize) {
-        if num_insts == self.set.capacity() {
+    /// Makes room for one thread per instruction, where each thread tracks
+    /// `slots_per_thread` capture slots.
+    ///
+    /// Threads only need as many slots as the caller asked for, so a search
+    /// that doesn't want any positions (like `is_match`) keeps no captures
+    /// at all.
+    fn resize(&mut self, num_insts: usize, slots_per_thread: usize) {
+        if num_insts == self.set.capacity()
+            && slots_per_thread == self.slots_per_thread {
             return;
         }
-        self.slots_per_thread = ncaps * 2;
-        self.set = SparseSet::new(num_insts);
-        self.caps = vec![None; self.slots_per_thread * num_insts];
+        self.slots_per_thread = slots_per_thread;
+        self.set.resize(num_insts);
+        self.caps.clear();
+        self.caps.resize(self.slots_per_thread * num_insts, None);
     }
 
     fn caps(&mut self, pc: usize) -> &mut [Option<usize>] {
@@ -8,6 +8,8 @@ use std::sync::Arc;
 
 use input::Char;
 use literals::LiteralSearcher;
+use sparse::SparseSet;
+use utf8::encode_utf8;
 
 /// InstPtr represents the index of an instruction in a regex program.
 pub type InstPtr = usize;
@@ -125,6 +127,287 @@ impl Program {
         self.only_utf8
     }
 
+    /// Returns true if at every `Split` instruction, at most one branch can
+    /// ever make progress on any given input.
+    ///
+    /// More precisely, for every `Split`, the set of characters (or bytes)
+    /// that can be consumed first by each branch must be disjoint, and at most
+    /// one branch may reach a `Match` instruction without consuming input.
+    /// Empty width assertions are conservatively assumed to always succeed.
+    ///
+    /// When this is true, the regex can be executed with a single thread.
+    pub fn is_deterministic(&self) -> bool {
+        let mut seen = SparseSet::new(self.len());
+        let mut stack = vec![];
+        for inst in &self.insts {
+            if let Inst::Split(ref inst) = *inst {
+                let (match1, ranges1) =
+                    self.first_set(inst.goto1, &mut seen, &mut stack);
+                let (match2, ranges2) =
+                    self.first_set(inst.goto2, &mut seen, &mut stack);
+                if match1 && match2 {
+                    return false;
+                }
+                for &(s1, e1) in &ranges1 {
+                    for &(s2, e2) in &ranges2 {
+                        if s1 <= e2 && s2 <= e1 {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Follows every epsilon transition starting at `pc` and returns whether
+    /// a `Match` instruction can be reached, along with the ranges of
+    /// codepoints (or bytes) matched by every consuming instruction reached.
+    ///
+    /// `seen` and `stack` are scratch space.
+    fn first_set(
+        &self,
+        pc: InstPtr,
+        seen: &mut SparseSet,
+        stack: &mut Vec<InstPtr>,
+    ) -> (bool, Vec<(u32, u32)>) {
+        let mut is_match = false;
+        let mut ranges = vec![];
+        seen.clear();
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if seen.contains_ip(pc) {
+                continue;
+            }
+            seen.add(pc);
+            match self[pc] {
+                Inst::Match(_) => is_match = true,
+                Inst::Save(ref inst) => stack.push(inst.goto),
+                Inst::EmptyLook(ref inst) => stack.push(inst.goto),
+                Inst::Split(ref inst) => {
+                    stack.push(inst.goto2);
+                    stack.push(inst.goto1);
+                }
+                Inst::Char(ref inst) => {
+                    ranges.push((inst.c as u32, inst.c as u32));
+                }
+                Inst::Ranges(ref inst) => {
+                    ranges.extend(inst.ranges.iter()
+                                      .map(|&(s, e)| (s as u32, e as u32)));
+                }
+                Inst::Bytes(ref inst) => {
+                    ranges.push((inst.start as u32, inst.end as u32));
+                }
+            }
+        }
+        (is_match, ranges)
+    }
+
+    /// Returns the worst case time complexity of searching text with this
+    /// program.
+    pub fn complexity_class(&self) -> ComplexityClass {
+        if self.is_empty()
+            || (self.is_anchored_start && !self.has_cycle()) {
+            ComplexityClass::O1
+        } else {
+            ComplexityClass::On
+        }
+    }
+
+    /// Returns true if there is a path from some instruction back to itself.
+    fn has_cycle(&self) -> bool {
+        // A depth first search, where an instruction is on the current path
+        // if it has been entered but not finished. Reaching an instruction on
+        // the current path means we've found a cycle.
+        let (unvisited, on_path, finished) = (0u8, 1u8, 2u8);
+        let mut state = vec![unvisited; self.len()];
+        let mut stack = vec![];
+        for root in 0..self.len() {
+            if state[root] != unvisited {
+                continue;
+            }
+            state[root] = on_path;
+            stack.push((root, 0));
+            while let Some((pc, next)) = stack.pop() {
+                let gotos = self.gotos(pc);
+                if next >= gotos.len() {
+                    state[pc] = finished;
+                    continue;
+                }
+                stack.push((pc, next + 1));
+                let goto = gotos[next];
+                if state[goto] == on_path {
+                    return true;
+                } else if state[goto] == unvisited {
+                    state[goto] = on_path;
+                    stack.push((goto, 0));
+                }
+            }
+        }
+        false
+    }
+
+    /// Returns the instructions that the given instruction can transition to.
+    fn gotos(&self, pc: InstPtr) -> Vec<InstPtr> {
+        match self[pc] {
+            Inst::Match(_) => vec![],
+            Inst::Save(ref inst) => vec![inst.goto],
+            Inst::Split(ref inst) => vec![inst.goto1, inst.goto2],
+            Inst::EmptyLook(ref inst) => vec![inst.goto],
+            Inst::Char(ref inst) => vec![inst.goto],
+            Inst::Ranges(ref inst) => vec![inst.goto],
+            Inst::Bytes(ref inst) => vec![inst.goto],
+        }
+    }
+
+    /// Returns an English description of every instruction in this program,
+    /// one per line.
+    pub fn explain(&self) -> String {
+        let mut out = String::new();
+        if self.start != 0 {
+            out.push_str(&format!("Starts at instruction {}.\n", self.start));
+        }
+        for (pc, inst) in self.iter().enumerate() {
+            let (what, goto) = match *inst {
+                Inst::Match(slot) => {
+                    let what = if self.matches.len() > 1 {
+                        format!("Reports a match of regex {}", slot)
+                    } else {
+                        "Reports a match".to_owned()
+                    };
+                    (what, None)
+                }
+                Inst::Save(ref inst) => {
+                    let group = inst.slot / 2;
+                    let name = match self.captures.get(group) {
+                        Some(&Some(ref name)) => format!(" ({})", name),
+                        _ => "".to_owned(),
+                    };
+                    let edge =
+                        if inst.slot % 2 == 0 { "Starts" } else { "Ends" };
+                    (format!("{} capture group {}{}", edge, group, name),
+                     Some(inst.goto))
+                }
+                Inst::Split(ref inst) => {
+                    (format!("Tries instruction {} first and then \
+                              instruction {}", inst.goto1, inst.goto2),
+                     None)
+                }
+                Inst::EmptyLook(ref inst) => {
+                    let look = match inst.look {
+                        EmptyLook::StartLine => "the start of a line",
+                        EmptyLook::EndLine => "the end of a line",
+                        EmptyLook::StartText => "the start of the text",
+                        EmptyLook::EndText => "the end of the text",
+                        EmptyLook::WordBoundary => "a Unicode word boundary",
+                        EmptyLook::NotWordBoundary => {
+                            "anything but a Unicode word boundary"
+                        }
+                        EmptyLook::WordBoundaryAscii => {
+                            "an ASCII word boundary"
+                        }
+                        EmptyLook::NotWordBoundaryAscii => {
+                            "anything but an ASCII word boundary"
+                        }
+                    };
+                    (format!("Matches {}", look), Some(inst.goto))
+                }
+                Inst::Char(ref inst) => {
+                    (format!("Matches the literal character {:?}", inst.c),
+                     Some(inst.goto))
+                }
+                Inst::Ranges(ref inst) => {
+                    let ranges = inst.ranges
+                        .iter()
+                        .map(|&(s, e)| if s == e {
+                            format!("{:?}", s)
+                        } else {
+                            format!("from {:?} to {:?}", s, e)
+                        })
+                        .collect::<Vec<String>>()
+                        .join(" or ");
+                    (format!("Matches any character {}", ranges),
+                     Some(inst.goto))
+                }
+                Inst::Bytes(ref inst) => {
+                    let what = if inst.start == inst.end {
+                        format!("Matches the byte \\x{:02X}", inst.start)
+                    } else {
+                        format!("Matches any byte from \\x{:02X} to \\x{:02X}",
+                                inst.start, inst.end)
+                    };
+                    (what, Some(inst.goto))
+                }
+            };
+            out.push_str(&format!("{}: {}", pc, what));
+            match goto {
+                Some(goto) if goto != pc + 1 => {
+                    out.push_str(&format!(", then goes to instruction {}",
+                                          goto));
+                }
+                _ => {}
+            }
+            out.push_str(".\n");
+        }
+        out
+    }
+
+    /// Returns every byte that a match of this program can start with, in
+    /// ascending order.
+    ///
+    /// The bytes are found by following the epsilon transitions from the
+    /// start of the program to the instructions that consume input. `None`
+    /// is returned if a match could start without consuming any input, or
+    /// if a zero-width assertion is reached first (since skipping ahead
+    /// would change what the assertion sees).
+    pub fn first_bytes(&self) -> Option<Vec<u8>> {
+        use self::Inst::*;
+
+        fn first_byte(c: char) -> usize {
+            let mut buf = [0; 4];
+            encode_utf8(c, &mut buf).unwrap();
+            buf[0] as usize
+        }
+
+        if self.insts.is_empty() {
+            return None;
+        }
+        let mut seen = vec![false; self.len()];
+        let mut bytes = vec![false; 256];
+        let mut stack = vec![self.start];
+        while let Some(pc) = stack.pop() {
+            if seen[pc] {
+                continue;
+            }
+            seen[pc] = true;
+            match self[pc] {
+                Match(_) | EmptyLook(_) => return None,
+                Save(ref inst) => stack.push(inst.goto),
+                Split(ref inst) => {
+                    stack.push(inst.goto2);
+                    stack.push(inst.goto1);
+                }
+                Char(ref inst) => bytes[first_byte(inst.c)] = true,
+                Ranges(ref inst) => {
+                    // The first byte of a UTF-8 encoded character grows with
+                    // the character, so a range of characters starts with a
+                    // range of bytes.
+                    for &(s, e) in &inst.ranges {
+                        for b in first_byte(s)..first_byte(e) + 1 {
+                            bytes[b] = true;
+                        }
+                    }
+                }
+                Bytes(ref inst) => {
+                    for b in inst.start as usize..inst.end as usize + 1 {
+                        bytes[b] = true;
+                    }
+                }
+            }
+        }
+        Some((0..256).filter(|&b| bytes[b]).map(|b| b as u8).collect())
+    }
+
     /// Return the approximate heap usage of this instruction sequence in
     /// bytes.
     pub fn approximate_size(&self) -> usize {
@@ -224,6 +507,18 @@ impl<'a> IntoIterator for &'a Program {
     fn into_iter(self) -> Self::IntoIter { self.iter() }
 }
 
+/// A static classification of the worst case time it takes to search text
+/// with a regex, in terms of the length of the text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComplexityClass {
+    /// The time to search is bounded by the size of the regex, regardless of
+    /// how long the text is. This is the case for regexes that are anchored
+    /// at the start of the text and contain no unbounded repetitions.
+    O1,
+    /// The time to search is linear in the length of the text.
+    On,
+}
+
 /// Inst is an instruction code in a Regex program.
 ///
 /// Regrettably, a regex program either contains Unicode codepoint
@@ -13,7 +13,7 @@ use std::collections::HashMap;
 use std::collections::hash_map;
 use std::fmt;
 use std::ops::Index;
-use std::str::FromStr;
+use std::str::{self, FromStr};
 use std::sync::Arc;
 
 use memchr::memchr;
@@ -24,6 +24,7 @@ use error::Error;
 use re_trait::{self, RegularExpression};
 
 pub use set::RegexSetBytes as RegexSet;
+pub use set::RegexSetBuilderBytes as RegexSetBuilder;
 pub use set::SetMatchesBytes as SetMatches;
 pub use set::SetMatchesIterBytes as SetMatchesIterBytes;
 pub use set::SetMatchesIntoIterBytes as SetMatchesIntoIterBytes;
@@ -119,6 +120,36 @@ impl Regex {
         self.shortest_match(text).is_some()
     }
 
+    /// Returns true if and only if the regex matches the text given and
+    /// every match (as found by `find_iter`) is valid UTF-8.
+    ///
+    /// Since valid UTF-8 can't encode surrogate code points, this checks
+    /// that everything matched is a sequence of Unicode scalar values. This
+    /// is useful for regexes that can match arbitrary bytes, like
+    /// `(?-u:.)`, when the matched text must later be used as a `&str`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::bytes::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?-u:\x22[^\x22]*\x22)").unwrap();
+    /// assert!(re.is_match_unicode_scalar(b"say \"h\xC3\xA9\""));
+    /// // An encoded surrogate code point.
+    /// assert!(!re.is_match_unicode_scalar(b"say \"\xED\xA0\x80\""));
+    /// # }
+    /// ```
+    pub fn is_match_unicode_scalar(&self, text: &[u8]) -> bool {
+        let mut matched = false;
+        for (s, e) in self.find_iter(text) {
+            if str::from_utf8(&text[s..e]).is_err() {
+                return false;
+            }
+            matched = true;
+        }
+        matched
+    }
+
     /// Returns the start and end byte range of the leftmost-first match in
     /// `text`. If no match exists, then `None` is returned.
     ///
@@ -243,6 +274,38 @@ impl Regex {
             })
     }
 
+    /// Returns the capture groups of the leftmost-first match in `text`, but
+    /// only if the text matched by every capture group is valid UTF-8.
+    ///
+    /// `None` is returned if there is no match or if any capture group that
+    /// participated in the match matched text that isn't valid UTF-8. This
+    /// means that it's safe to convert each captured slice with
+    /// `std::str::from_utf8(..).unwrap()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::bytes::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?-u)(\w+)=(.*)").unwrap();
+    /// let caps = re.captures_unicode_verified(b"k=\xE2\x98\x83").unwrap();
+    /// assert_eq!(caps.at(2), Some(&b"\xE2\x98\x83"[..]));
+    /// assert!(re.captures_unicode_verified(b"k=\xE2\x98").is_none());
+    /// # }
+    /// ```
+    pub fn captures_unicode_verified<'t>(
+        &self,
+        text: &'t [u8],
+    ) -> Option<Captures<'t>> {
+        self.captures(text).and_then(|caps| {
+            let valid = caps.iter().all(|group| match group {
+                None => true,
+                Some(group) => str::from_utf8(group).is_ok(),
+            });
+            if valid { Some(caps) } else { None }
+        })
+    }
+
     /// Returns an iterator over all the non-overlapping capture groups matched
     /// in `text`. This is operationally the same as `find_iter`, except it
     /// yields information about submatches.
@@ -510,6 +573,13 @@ impl Regex {
     pub fn captures_len(&self) -> usize {
         self.0.capture_names().len()
     }
+
+    /// Returns the number of capture slots, which is two (a start and an end
+    /// position) for each capture, including the implicit capture of the
+    /// entire match.
+    pub fn capture_slot_count(&self) -> usize {
+        self.captures_len() * 2
+    }
 }
 
 /// An iterator over all non-overlapping matches for a particular string.
@@ -9,18 +9,28 @@
 // except according to those terms.
 
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::env;
 use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::iter;
 use std::ops::Index;
+use std::path::Path;
+use std::slice;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::vec;
 
 use syntax;
 
+use dfa::DfaTable;
 use exec::{Exec, ExecNoSyncStr, ExecBuilder};
-use error::Error;
+use error::{Error, RegexEnvError, StepLimitExceeded};
+use prog::ComplexityClass;
 use re_plugin::Plugin;
-use re_trait::{self, RegularExpression};
+use re_trait::{self, RegularExpression, Slot};
 
 /// Escapes all regular expression meta characters in `text`.
 ///
@@ -41,6 +51,170 @@ pub fn is_match(regex: &str, text: &str) -> Result<bool, Error> {
     Regex::new(regex).map(|r| r.is_match(text))
 }
 
+/// Returns true if the byte index `i` is on a character boundary of `text`.
+///
+/// This is `str::is_char_boundary`, which needs a newer Rust than this crate
+/// supports.
+fn is_char_boundary(text: &str, i: usize) -> bool {
+    i == text.len() || (i < text.len() && text.as_bytes()[i] & 0xC0 != 0x80)
+}
+
+/// Replaces every capture group in `expr` with a non-capturing group.
+fn strip_captures(expr: syntax::Expr) -> syntax::Expr {
+    use syntax::Expr::*;
+    match expr {
+        Group { e, .. } => Group {
+            e: Box::new(strip_captures(*e)),
+            i: None,
+            name: None,
+        },
+        Repeat { e, r, greedy } => Repeat {
+            e: Box::new(strip_captures(*e)),
+            r: r,
+            greedy: greedy,
+        },
+        Concat(es) => Concat(es.into_iter().map(strip_captures).collect()),
+        Alternate(es) => {
+            Alternate(es.into_iter().map(strip_captures).collect())
+        }
+        e => e,
+    }
+}
+
+/// Applies one round of simplification rules to every node in `expr`.
+///
+/// Every rule preserves both the matches and the capture groups of `expr`.
+fn simplify_expr(expr: syntax::Expr) -> syntax::Expr {
+    use syntax::Expr::*;
+    use syntax::Repeater::*;
+    match expr {
+        Group { e, i: None, name: None } => {
+            let e = simplify_expr(*e);
+            // Groups and single atoms don't need another group.
+            if is_atom(&e) {
+                e
+            } else {
+                Group { e: Box::new(e), i: None, name: None }
+            }
+        }
+        Group { e, i, name } => Group {
+            e: Box::new(simplify_expr(*e)),
+            i: i,
+            name: name,
+        },
+        Repeat { e, r, greedy } => {
+            let e = simplify_expr(*e);
+            if r == (Range { min: 1, max: Some(1) }) {
+                return e;
+            }
+            // Nested repetitions like `(?:a?)+` collapse into one.
+            let inner = match e {
+                Group { e, i: None, name: None } => *e,
+                e => e,
+            };
+            match inner {
+                Repeat { e, r: r2, greedy: greedy2 }
+                    if greedy == greedy2 && is_simple(r) && is_simple(r2) => {
+                    let r = if r == r2 { r } else { ZeroOrMore };
+                    Repeat { e: e, r: r, greedy: greedy }
+                }
+                Repeat { e: inner_e, r: r2, greedy: greedy2 } => Repeat {
+                    e: Box::new(Group {
+                        e: Box::new(Repeat {
+                            e: inner_e,
+                            r: r2,
+                            greedy: greedy2,
+                        }),
+                        i: None,
+                        name: None,
+                    }),
+                    r: r,
+                    greedy: greedy,
+                },
+                inner => Repeat {
+                    e: Box::new(if is_atom(&inner) {
+                        inner
+                    } else {
+                        Group { e: Box::new(inner), i: None, name: None }
+                    }),
+                    r: r,
+                    greedy: greedy,
+                },
+            }
+        }
+        Concat(es) => {
+            let mut flat = vec![];
+            for e in es.into_iter().map(simplify_expr) {
+                match e {
+                    Empty => {}
+                    Concat(es) => flat.extend(es),
+                    Group { e, i: None, name: None } => match *e {
+                        Concat(es) => flat.extend(es),
+                        e @ Alternate(_) => flat.push(Group {
+                            e: Box::new(e),
+                            i: None,
+                            name: None,
+                        }),
+                        e => flat.push(e),
+                    },
+                    e => flat.push(e),
+                }
+            }
+            match flat.len() {
+                0 => Empty,
+                1 => flat.pop().unwrap(),
+                _ => Concat(flat),
+            }
+        }
+        Alternate(es) => {
+            // Leftmost-first matching always prefers the first of two
+            // identical branches, so later copies can be dropped.
+            let mut flat: Vec<syntax::Expr> = vec![];
+            for e in es.into_iter().map(simplify_expr) {
+                let es = match e {
+                    Alternate(es) => es,
+                    Group { e, i: None, name: None } => match *e {
+                        Alternate(es) => es,
+                        e => vec![e],
+                    },
+                    e => vec![e],
+                };
+                for e in es {
+                    if !flat.contains(&e) {
+                        flat.push(e);
+                    }
+                }
+            }
+            if flat.len() == 1 {
+                flat.pop().unwrap()
+            } else {
+                Alternate(flat)
+            }
+        }
+        e => e,
+    }
+}
+
+/// Returns true if `r` is one of `?`, `*` or `+`.
+fn is_simple(r: syntax::Repeater) -> bool {
+    match r {
+        syntax::Repeater::Range { .. } => false,
+        _ => true,
+    }
+}
+
+/// Returns true if `expr` can be repeated without being put in a group.
+fn is_atom(expr: &syntax::Expr) -> bool {
+    use syntax::Expr::*;
+    match *expr {
+        Literal { .. } | LiteralBytes { .. } | AnyChar | AnyCharNoNL
+        | AnyByte | AnyByteNoNL | Class(_) | ClassBytes(_) | Group { .. } => {
+            true
+        }
+        _ => false,
+    }
+}
+
 /// A compiled regular expression for matching Unicode strings.
 ///
 /// It is represented as either a sequence of bytecode instructions (dynamic)
@@ -172,6 +346,41 @@ impl Regex {
         ExecBuilder::new(re).size_limit(size).build().map(Regex::from)
     }
 
+    /// Compiles the regular expression stored in the environment variable
+    /// `var`.
+    ///
+    /// An error is returned if the variable isn't set, if its value isn't
+    /// valid UTF-8 or if its value isn't a valid regular expression.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::{Regex, RegexEnvError};
+    /// # fn main() {
+    /// std::env::set_var("MY_APP_FILTER", r"\d+");
+    /// let re = Regex::try_from_env("MY_APP_FILTER").unwrap();
+    /// assert!(re.is_match("42"));
+    ///
+    /// match Regex::try_from_env("MY_APP_UNSET") {
+    ///     Err(RegexEnvError::NotPresent(var)) => {
+    ///         assert_eq!(var, "MY_APP_UNSET");
+    ///     }
+    ///     _ => unreachable!(),
+    /// }
+    /// # }
+    /// ```
+    pub fn try_from_env(var: &str) -> Result<Regex, RegexEnvError> {
+        match env::var(var) {
+            Ok(re) => Ok(try!(Regex::new(&re))),
+            Err(env::VarError::NotPresent) => {
+                Err(RegexEnvError::NotPresent(var.to_owned()))
+            }
+            Err(env::VarError::NotUnicode(value)) => {
+                Err(RegexEnvError::NotUtf8(value))
+            }
+        }
+    }
+
     /// Returns true if and only if the regex matches the string given.
     ///
     /// It is recommended to use this method if all you need to do is test
@@ -223,404 +432,2231 @@ impl Regex {
         }
     }
 
-    /// Returns an iterator for each successive non-overlapping match in
-    /// `text`, returning the start and end byte indices with respect to
-    /// `text`.
+    /// Returns the start and end byte range of the leftmost-first match in
+    /// `text` that starts at or after the byte index `start`.
     ///
-    /// # Example
+    /// This is different from calling `find` on `&text[start..]`, since
+    /// look-behind assertions like `\b` and `^` still see the text before
+    /// `start`. The match returned is with respect to all of `text`.
     ///
-    /// Find the start and end location of every word with exactly 13 Unicode
-    /// word characters:
+    /// # Panics
+    ///
+    /// If `start` is greater than `text.len()` or isn't on a character
+    /// boundary.
+    ///
+    /// # Example
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let text = "Retroactively relinquishing remunerations is reprehensible.";
-    /// for pos in Regex::new(r"\b\w{13}\b").unwrap().find_iter(text) {
-    ///     println!("{:?}", pos);
-    /// }
-    /// // Output:
-    /// // (0, 13)
-    /// // (14, 27)
-    /// // (28, 41)
-    /// // (45, 58)
+    /// let re = Regex::new(r"\bcat").unwrap();
+    /// let text = "concat cat";
+    /// assert_eq!(re.find(&text[3..]), Some((0, 3)));
+    /// assert_eq!(re.find_at(text, 3), Some((7, 10)));
     /// # }
     /// ```
-    pub fn find_iter<'r, 't>(&'r self, text: &'t str) -> FindMatches<'r, 't> {
+    pub fn find_at(&self, text: &str, start: usize) -> Option<(usize, usize)> {
+        assert!(is_char_boundary(text, start),
+                "start {} is not a character boundary of the text", start);
         match self.0 {
             _Regex::Dynamic(ref exec) => {
-                let it = exec.searcher_str().find_iter(text);
-                FindMatches(FindMatchesInner::Dynamic(it))
-            }
-            _Regex::Plugin(ref plug) => {
-                let it = plug.find_iter(text);
-                FindMatches(FindMatchesInner::Plugin(it))
+                exec.searcher_str().find_at(text, start)
             }
+            _Regex::Plugin(ref plug) => plug.find_at(text, start),
         }
     }
 
-    /// Returns the capture groups corresponding to the leftmost-first
-    /// match in `text`. Capture group `0` always corresponds to the entire
-    /// match. If no match is found, then `None` is returned.
+    /// Returns the start and end byte range of the leftmost-first match in
+    /// `text` that starts exactly at the byte index `start`.
     ///
-    /// You should only use `captures` if you need access to submatches.
-    /// Otherwise, `find` is faster for discovering the location of the overall
-    /// match.
+    /// This is useful for lexers, which only want a token if it begins at
+    /// the current position. Unlike `find_at`, matches that start later are
+    /// never considered. Look-behind assertions like `\b` and `^` still see
+    /// the text before `start`.
     ///
-    /// # Examples
+    /// # Panics
     ///
-    /// Say you have some text with movie names and their release years,
-    /// like "'Citizen Kane' (1941)". It'd be nice if we could search for text
-    /// looking like that, while also extracting the movie name and its release
-    /// year separately.
+    /// If `start` is greater than `text.len()` or isn't on a character
+    /// boundary.
+    ///
+    /// # Example
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let re = Regex::new(r"'([^']+)'\s+\((\d{4})\)").unwrap();
-    /// let text = "Not my favorite movie: 'Citizen Kane' (1941).";
-    /// let caps = re.captures(text).unwrap();
-    /// assert_eq!(caps.at(1), Some("Citizen Kane"));
-    /// assert_eq!(caps.at(2), Some("1941"));
-    /// assert_eq!(caps.at(0), Some("'Citizen Kane' (1941)"));
-    /// // You can also access the groups by index using the Index notation.
-    /// // Note that this will panic on an invalid index.
-    /// assert_eq!(&caps[1], "Citizen Kane");
-    /// assert_eq!(&caps[2], "1941");
-    /// assert_eq!(&caps[0], "'Citizen Kane' (1941)");
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let text = "x = 42";
+    /// assert_eq!(re.find_at(text, 0), Some((4, 6)));
+    /// assert_eq!(re.find_anchored_at(text, 0), None);
+    /// assert_eq!(re.find_anchored_at(text, 4), Some((4, 6)));
     /// # }
     /// ```
+    pub fn find_anchored_at(
+        &self,
+        text: &str,
+        start: usize,
+    ) -> Option<(usize, usize)> {
+        assert!(is_char_boundary(text, start),
+                "start {} is not a character boundary of the text", start);
+        // Plugin regexes are validated at compile time, so recompiling one
+        // can't fail.
+        self.to_exec().unwrap().find_anchored_at(text.as_bytes(), start)
+    }
+
+    /// Returns the start and end byte range of the last match in `text`.
+    /// If no match exists, then `None` is returned.
     ///
-    /// Note that the full match is at capture group `0`. Each subsequent
-    /// capture group is indexed by the order of its opening `(`.
+    /// The match returned is the last one that `find_iter` would yield, so
+    /// it never overlaps an earlier match. Currently this searches `text`
+    /// from the beginning.
     ///
-    /// We can make this example a bit clearer by using *named* capture groups:
+    /// # Example
+    ///
+    /// Find the last path separator:
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let re = Regex::new(r"'(?P<title>[^']+)'\s+\((?P<year>\d{4})\)")
-    ///                .unwrap();
-    /// let text = "Not my favorite movie: 'Citizen Kane' (1941).";
-    /// let caps = re.captures(text).unwrap();
-    /// assert_eq!(caps.name("title"), Some("Citizen Kane"));
-    /// assert_eq!(caps.name("year"), Some("1941"));
-    /// assert_eq!(caps.at(0), Some("'Citizen Kane' (1941)"));
-    /// // You can also access the groups by name using the Index notation.
-    /// // Note that this will panic on an invalid group name.
-    /// assert_eq!(&caps["title"], "Citizen Kane");
-    /// assert_eq!(&caps["year"], "1941");
-    /// assert_eq!(&caps[0], "'Citizen Kane' (1941)");
-    ///
+    /// let re = Regex::new(r"[/\\]+").unwrap();
+    /// assert_eq!(re.rfind("usr//local/bin"), Some((10, 11)));
+    /// assert_eq!(re.rfind("bin"), None);
     /// # }
     /// ```
-    ///
-    /// Here we name the capture groups, which we can access with the `name`
-    /// method or the `Index` notation with a `&str`. Note that the named
-    /// capture groups are still accessible with `at` or the `Index` notation
-    /// with a `usize`.
-    ///
-    /// The `0`th capture group is always unnamed, so it must always be
-    /// accessed with `at(0)` or `[0]`.
-    pub fn captures<'t>(&self, text: &'t str) -> Option<Captures<'t>> {
-        let mut slots = vec![None; 2 * self.captures_len()];
-        let result = match self.0 {
-            _Regex::Dynamic(ref exec) => {
-                exec.searcher_str().captures_at(&mut slots, text, 0)
-            }
-            _Regex::Plugin(ref plug) => plug.captures_at(&mut slots, text, 0),
-        };
-        result.map(|_| Captures {
-            text: text,
-            slots: slots,
-            named_groups: NamedGroups::from_regex(self)
-        })
+    pub fn rfind(&self, text: &str) -> Option<(usize, usize)> {
+        self.find_iter(text).last()
     }
 
-    /// Returns an iterator over all the non-overlapping capture groups matched
-    /// in `text`. This is operationally the same as `find_iter`, except it
-    /// yields information about submatches.
+    /// Returns the start and end byte range of the leftmost-first match in
+    /// `text` that starts at or after the character index `char_pos`.
     ///
-    /// # Example
+    /// `char_pos` counts Unicode scalar values, as produced by
+    /// `text.chars().enumerate()`, while the match returned is in byte
+    /// indices like every other search method. `char_pos` may be equal to
+    /// the number of characters in `text` to search at the end of the text.
+    /// If it's larger than that, `None` is returned.
     ///
-    /// We can use this to find all movie titles and their release years in
-    /// some text, where the movie is formatted like "'Title' (xxxx)":
+    /// Note that look-behind assertions like `\b` or `^` still see the text
+    /// before `char_pos`.
+    ///
+    /// # Example
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let re = Regex::new(r"'(?P<title>[^']+)'\s+\((?P<year>\d{4})\)")
-    ///                .unwrap();
-    /// let text = "'Citizen Kane' (1941), 'The Wizard of Oz' (1939), 'M' (1931).";
-    /// for caps in re.captures_iter(text) {
-    ///     println!("Movie: {:?}, Released: {:?}",
-    ///              caps.name("title"), caps.name("year"));
-    /// }
-    /// // Output:
-    /// // Movie: Citizen Kane, Released: 1941
-    /// // Movie: The Wizard of Oz, Released: 1939
-    /// // Movie: M, Released: 1931
+    /// let re = Regex::new(r"\w+").unwrap();
+    /// assert_eq!(re.find_at_char("δύο λέξεις", 4), Some((7, 19)));
     /// # }
     /// ```
-    pub fn captures_iter<'r, 't>(
-        &'r self,
-        text: &'t str,
-    ) -> FindCaptures<'r, 't> {
-        match self.0 {
-            _Regex::Dynamic(ref exec) => {
-                let it = exec.searcher_str().captures_iter(text);
-                FindCaptures(FindCapturesInner::Dynamic(it))
-            }
-            _Regex::Plugin(ref plug) => {
-                let it = plug.captures_iter(text);
-                FindCaptures(FindCapturesInner::Plugin(it))
-            }
-        }
+    pub fn find_at_char(
+        &self,
+        text: &str,
+        char_pos: usize,
+    ) -> Option<(usize, usize)> {
+        let start = match text.char_indices()
+                              .map(|(i, _)| i)
+                              .chain(Some(text.len()))
+                              .nth(char_pos) {
+            None => return None,
+            Some(start) => start,
+        };
+        self.find_at(text, start)
     }
 
-    /// Returns an iterator of substrings of `text` delimited by a match of the
-    /// regular expression. Namely, each element of the iterator corresponds to
-    /// text that *isn't* matched by the regular expression.
+    /// Returns, for every byte position `i` in `text`, the leftmost-first
+    /// match that starts at `i`, if there is one.
     ///
-    /// This method will *not* copy the text given.
+    /// The vector returned has one element for each byte in `text`. An
+    /// element is `None` if no match starts at its position, which is always
+    /// the case for positions that aren't at the start of a UTF-8 encoded
+    /// character.
     ///
-    /// # Example
+    /// Unlike `find_iter`, the matches returned may overlap.
     ///
-    /// To split a string delimited by arbitrary amounts of spaces or tabs:
+    /// # Example
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let re = Regex::new(r"[ \t]+").unwrap();
-    /// let fields: Vec<&str> = re.split("a b \t  c\td    e").collect();
-    /// assert_eq!(fields, vec!["a", "b", "c", "d", "e"]);
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// assert_eq!(re.matches_at_every_position("a12"),
+    ///            vec![None, Some((1, 3)), Some((2, 3))]);
     /// # }
     /// ```
-    pub fn split<'r, 't>(&'r self, text: &'t str) -> RegexSplits<'r, 't> {
-        RegexSplits {
-            finder: self.find_iter(text),
-            last: 0,
+    pub fn matches_at_every_position(
+        &self,
+        text: &str,
+    ) -> Vec<Option<(usize, usize)>> {
+        let mut matches = vec![None; text.len()];
+        for (pos, m) in self.find_iter_with_positions(text) {
+            matches[pos] = m;
         }
+        matches
     }
 
-    /// Returns an iterator of at most `limit` substrings of `text` delimited
-    /// by a match of the regular expression. (A `limit` of `0` will return no
-    /// substrings.) Namely, each element of the iterator corresponds to text
-    /// that *isn't* matched by the regular expression. The remainder of the
-    /// string that is not split will be the last element in the iterator.
+    /// Returns an iterator that yields, for every position `i` in `text`
+    /// that is at the start of a UTF-8 encoded character, `i` and the
+    /// leftmost-first match that starts at `i`, if there is one.
     ///
-    /// This method will *not* copy the text given.
+    /// This is the lazy version of `matches_at_every_position`. Matches are
+    /// only searched for as the iterator advances, and one search covers
+    /// every position up to the start of the match it finds.
     ///
     /// # Example
     ///
-    /// Get the first two words in some text:
-    ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let re = Regex::new(r"\W+").unwrap();
-    /// let fields: Vec<&str> = re.splitn("Hey! How are you?", 3).collect();
-    /// assert_eq!(fields, vec!("Hey", "How", "are you?"));
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let found: Vec<_> = re.find_iter_with_positions("é12").collect();
+    /// assert_eq!(found, vec![
+    ///     (0, None),
+    ///     (2, Some((2, 4))),
+    ///     (3, Some((3, 4))),
+    /// ]);
     /// # }
     /// ```
-    pub fn splitn<'r, 't>(&'r self, text: &'t str, limit: usize)
-                         -> RegexSplitsN<'r, 't> {
-        RegexSplitsN {
-            splits: self.split(text),
-            n: limit,
+    pub fn find_iter_with_positions<'r, 't>(
+        &'r self,
+        text: &'t str,
+    ) -> MatchPositions<'r, 't> {
+        MatchPositions {
+            re: self,
+            text: text,
+            pos: 0,
+            next_match: None,
+            done: false,
         }
     }
 
-    /// Replaces the leftmost-first match with the replacement provided.
-    /// The replacement can be a regular string (where `$N` and `$name` are
-    /// expanded to match capture groups) or a function that takes the matches'
-    /// `Captures` and returns the replaced string.
+    /// Returns the start and end byte range of the leftmost-first match in
+    /// `text[start..end]`. If no match exists, then `None` is returned.
     ///
-    /// If no match is found, then a copy of the string is returned unchanged.
+    /// The regex never looks at text outside of the window, so the window's
+    /// bounds are treated as the bounds of the text. For example, `^` and
+    /// `$` match at `start` and `end`. The positions returned are relative
+    /// to the start of `text`, not the start of the window.
     ///
-    /// # Examples
+    /// # Panics
     ///
-    /// Note that this function is polymorphic with respect to the replacement.
-    /// In typical usage, this can just be a normal string:
+    /// If `start..end` is not a valid range of `text` whose bounds lie on
+    /// UTF-8 character boundaries.
+    ///
+    /// # Example
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let re = Regex::new("[^01]+").unwrap();
-    /// assert_eq!(re.replace("1078910", ""), "1010");
+    /// let re = Regex::new(r"[0-9]+$").unwrap();
+    /// assert_eq!(re.find_between_positions("a12b345", 1, 3), Some((1, 3)));
     /// # }
     /// ```
+    pub fn find_between_positions(
+        &self,
+        text: &str,
+        start: usize,
+        end: usize,
+    ) -> Option<(usize, usize)> {
+        self.find(&text[start..end]).map(|(s, e)| (start + s, start + e))
+    }
+
+    /// Returns every successive non-overlapping match in `text[start..end]`.
     ///
-    /// But anything satisfying the `Replacer` trait will work. For example,
-    /// a closure of type `|&Captures| -> String` provides direct access to the
-    /// captures corresponding to a match. This allows one to access
-    /// submatches easily:
+    /// Like `find_between_positions`, the window's bounds are treated as the
+    /// bounds of the text and the positions returned are relative to the
+    /// start of `text`.
+    ///
+    /// # Panics
+    ///
+    /// If `start..end` is not a valid range of `text` whose bounds lie on
+    /// UTF-8 character boundaries.
+    ///
+    /// # Example
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
-    /// # use regex::Captures; fn main() {
-    /// let re = Regex::new(r"([^,\s]+),\s+(\S+)").unwrap();
-    /// let result = re.replace("Springsteen, Bruce", |caps: &Captures| {
-    ///     format!("{} {}", caps.at(2).unwrap_or(""), caps.at(1).unwrap_or(""))
-    /// });
-    /// assert_eq!(result, "Bruce Springsteen");
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let matches = re.find_all_between_positions("1 23 456", 3, 7);
+    /// assert_eq!(matches, vec![(3, 4), (5, 7)]);
     /// # }
     /// ```
+    pub fn find_all_between_positions(
+        &self,
+        text: &str,
+        start: usize,
+        end: usize,
+    ) -> Vec<(usize, usize)> {
+        self.find_iter(&text[start..end])
+            .map(|(s, e)| (start + s, start + e))
+            .collect()
+    }
+
+    /// Returns an iterator that searches each `window`-byte window of `text`,
+    /// with consecutive windows starting `stride` bytes apart.
     ///
-    /// But this is a bit cumbersome to use all the time. Instead, a simple
-    /// syntax is supported that expands `$name` into the corresponding capture
-    /// group. Here's the last example, but using this expansion technique
-    /// with named capture groups:
+    /// For each window, the iterator yields the leftmost-first match in that
+    /// window as found by `find_between_positions`, or `None` if there is no
+    /// match in it. The first window starts at the beginning of `text` and
+    /// the last window is the first one that reaches the end of `text`.
+    ///
+    /// A window boundary that falls inside a UTF-8 encoded character is
+    /// moved to the end of the character for the start of a window, and to
+    /// the start of the character for the end of a window.
+    ///
+    /// # Panics
+    ///
+    /// If `stride` is `0`.
+    ///
+    /// # Example
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let re = Regex::new(r"(?P<last>[^,\s]+),\s+(?P<first>\S+)").unwrap();
-    /// let result = re.replace("Springsteen, Bruce", "$first $last");
-    /// assert_eq!(result, "Bruce Springsteen");
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let found: Vec<_> = re.find_iter_windows("ab12cdef3", 4, 3).collect();
+    /// assert_eq!(found, vec![Some((2, 4)), Some((3, 4)), Some((8, 9))]);
     /// # }
     /// ```
+    pub fn find_iter_windows<'r, 't>(
+        &'r self,
+        text: &'t str,
+        window: usize,
+        stride: usize,
+    ) -> MatchWindows<'r, 't> {
+        assert!(stride > 0, "stride must be greater than 0");
+        MatchWindows {
+            re: self,
+            text: text,
+            window: window,
+            stride: stride,
+            next_start: Some(0),
+        }
+    }
+
+    /// Returns every successive non-overlapping match in `text` for which
+    /// `pred` returns `true`.
     ///
-    /// Note that using `$2` instead of `$first` or `$1` instead of `$last`
-    /// would produce the same result. To write a literal `$` use `$$`.
+    /// `pred` is given the start and end byte indices of each match, in the
+    /// order they are found by `find_iter`. Rejected matches still count as
+    /// matches when searching for the next one, so they never overlap with
+    /// the matches returned.
     ///
-    /// Finally, sometimes you just want to replace a literal string with no
-    /// submatch expansion. This can be done by wrapping a string with
-    /// `NoExpand`:
+    /// # Example
+    ///
+    /// Find every word that isn't capitalized:
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// use regex::NoExpand;
+    /// let re = Regex::new(r"\w+").unwrap();
+    /// let text = "Hello big World";
+    /// let found = re.find_all_matching_predicate(text, |(s, e)| {
+    ///     text[s..e].chars().all(char::is_lowercase)
+    /// });
+    /// assert_eq!(found, vec![(6, 9)]);
+    /// # }
+    /// ```
+    pub fn find_all_matching_predicate<F>(
+        &self,
+        text: &str,
+        mut pred: F,
+    ) -> Vec<(usize, usize)>
+            where F: FnMut((usize, usize)) -> bool {
+        self.find_iter(text).filter(|&m| pred(m)).collect()
+    }
+
+    /// Returns successive non-overlapping matches in `text` until `fuel` runs
+    /// out, along with the fuel that is left.
     ///
-    /// let re = Regex::new(r"(?P<last>[^,\s]+),\s+(\S+)").unwrap();
-    /// let result = re.replace("Springsteen, Bruce", NoExpand("$2 $last"));
-    /// assert_eq!(result, "$2 $last");
+    /// Each match costs one unit of fuel for every byte of `text` between the
+    /// end of the previous match and its own end, and at least one unit, so
+    /// that empty matches aren't free. If there isn't enough fuel left for a
+    /// match, the search stops, the match isn't returned and the remaining
+    /// fuel is zero. Otherwise, once there are no more matches, the rest of
+    /// the text is charged for as well (or whatever fuel is left, if that's
+    /// less).
+    ///
+    /// This makes it possible to share one budget between several searches.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let text = "1 22 333 4444";
+    /// assert_eq!(re.find_all_with_fuel(text, 5),
+    ///            (vec![(0, 1), (2, 4)], 0));
+    /// assert_eq!(re.find_all_with_fuel(text, 100),
+    ///            (vec![(0, 1), (2, 4), (5, 8), (9, 13)], 87));
     /// # }
     /// ```
-    pub fn replace<R: Replacer>(&self, text: &str, rep: R) -> String {
-        self.replacen(text, 1, rep)
+    pub fn find_all_with_fuel(
+        &self,
+        text: &str,
+        mut fuel: u64,
+    ) -> (Vec<(usize, usize)>, u64) {
+        let mut matches = vec![];
+        let mut last_end = 0;
+        for (s, e) in self.find_iter(text) {
+            let cost = cmp::max(e - last_end, 1) as u64;
+            if cost > fuel {
+                return (matches, 0);
+            }
+            fuel -= cost;
+            matches.push((s, e));
+            last_end = e;
+        }
+        let rest = (text.len() - last_end) as u64;
+        (matches, fuel.saturating_sub(rest))
     }
 
-    /// Replaces all non-overlapping matches in `text` with the replacement
-    /// provided. This is the same as calling `replacen` with `limit` set to
-    /// `0`.
+    /// Returns the start and end byte indices of the leftmost-first match in
+    /// `text`, giving up once the search has visited more than `limit`
+    /// instructions of the compiled program.
     ///
-    /// See the documentation for `replace` for details on how to access
-    /// submatches in the replacement string.
-    pub fn replace_all<R: Replacer>(&self, text: &str, rep: R) -> String {
-        self.replacen(text, 0, rep)
+    /// The search always runs on the NFA simulation, whose work is linear in
+    /// the size of the text, but that can still be a lot of work for long
+    /// texts. This puts a ceiling on it. An error is returned if the search
+    /// runs out of steps before it finds the match or rules it out. Text
+    /// that is skipped while looking for a literal prefix of the regex
+    /// doesn't cost any steps.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # use std::iter;
+    /// # fn main() {
+    /// let re = Regex::new(r"\w+!").unwrap();
+    /// assert_eq!(re.find_with_step_limit("hi!", 1000), Ok(Some((0, 3))));
+    /// let long = iter::repeat("a").take(1000).collect::<String>();
+    /// assert!(re.find_with_step_limit(&long, 100).is_err());
+    /// # }
+    /// ```
+    pub fn find_with_step_limit(
+        &self,
+        text: &str,
+        limit: u64,
+    ) -> Result<Option<(usize, usize)>, StepLimitExceeded> {
+        // Plugin regexes are validated at compile time, so recompiling one
+        // can't fail.
+        self.to_exec().unwrap().find_limited(text.as_bytes(), limit)
     }
 
-    /// Replaces at most `limit` non-overlapping matches in `text` with the
-    /// replacement provided. If `limit` is 0, then all non-overlapping matches
-    /// are replaced.
+    /// Returns an iterator for each successive non-overlapping match in
+    /// `text`, returning the start and end byte indices with respect to
+    /// `text`.
     ///
-    /// See the documentation for `replace` for details on how to access
-    /// submatches in the replacement string.
-    pub fn replacen<R: Replacer>(
+    /// # Example
+    ///
+    /// Find the start and end location of every word with exactly 13 Unicode
+    /// word characters:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let text = "Retroactively relinquishing remunerations is reprehensible.";
+    /// for pos in Regex::new(r"\b\w{13}\b").unwrap().find_iter(text) {
+    ///     println!("{:?}", pos);
+    /// }
+    /// // Output:
+    /// // (0, 13)
+    /// // (14, 27)
+    /// // (28, 41)
+    /// // (45, 58)
+    /// # }
+    /// ```
+    pub fn find_iter<'r, 't>(&'r self, text: &'t str) -> FindMatches<'r, 't> {
+        match self.0 {
+            _Regex::Dynamic(ref exec) => {
+                let it = exec.searcher_str().find_iter(text);
+                FindMatches(FindMatchesInner::Dynamic(it))
+            }
+            _Regex::Plugin(ref plug) => {
+                let it = plug.find_iter(text);
+                FindMatches(FindMatchesInner::Plugin(it))
+            }
+        }
+    }
+
+    /// Returns an iterator for each successive non-overlapping match in the
+    /// bytes of a memory mapped file, returning the start and end byte
+    /// indices with respect to `mmap`.
+    ///
+    /// The bytes are searched in place and aren't required to be valid
+    /// UTF-8. Matches are found lazily, one at a time, so only the parts of
+    /// `mmap` that are actually searched are ever paged in.
+    ///
+    /// Since the search runs on bytes, the regex is compiled again to a byte
+    /// based program the first time this is called. Classes like `\w` keep
+    /// their Unicode meaning. An error is returned if that program exceeds
+    /// the size limit this regex was compiled with.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let mmap: &[u8] = b"ab\xFF12\n345";
+    /// let found: Vec<_> = re.exec_on_mmap(mmap).unwrap().collect();
+    /// assert_eq!(found, vec![(3, 5), (6, 9)]);
+    /// # }
+    /// ```
+    pub fn exec_on_mmap<'t>(
+        &self,
+        mmap: &'t [u8],
+    ) -> Result<MmapMatches<'t>, Error> {
+        let exec = match self.0 {
+            _Regex::Dynamic(ref exec) => try!(exec.any_bytes()),
+            _Regex::Plugin(_) => {
+                let exec = try!(ExecBuilder::new(self.as_str())
+                    .bytes(true)
+                    .only_utf8(false)
+                    .unicode(true)
+                    .build());
+                Arc::new(exec)
+            }
+        };
+        Ok(MmapMatches {
+            exec: exec,
+            text: mmap,
+            last_end: 0,
+            last_match: None,
+        })
+    }
+
+    /// Returns the line numbers of every line in `text` on which a match
+    /// starts, in ascending order and without duplicates.
+    ///
+    /// Lines are separated by `\n` and numbered from 1. The whole text is
+    /// searched at once, so a match may extend past the end of its line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"error").unwrap();
+    /// let text = "error: a\nok\nerror: b, error: c\n";
+    /// assert_eq!(re.find_line_numbers(text), vec![1, 3]);
+    /// # }
+    /// ```
+    pub fn find_line_numbers(&self, text: &str) -> Vec<usize> {
+        let mut lines = vec![];
+        let mut line = 1;
+        let mut counted = 0;
+        for (s, _) in self.find_iter(text) {
+            line += text[counted..s].bytes().filter(|&b| b == b'\n').count();
+            counted = s;
+            if lines.last() != Some(&line) {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    /// Returns the first line in `text` on which a match starts, along with
+    /// its line number and the start and end byte indices of the match.
+    ///
+    /// Lines are separated by `\n` and numbered from 1, like in
+    /// `find_line_numbers`, but the search stops at the first match. The
+    /// returned line doesn't include its `\n`, and the match indices are
+    /// with respect to `text`, not the line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"error").unwrap();
+    /// let text = "ok\nan error\nerror again";
+    /// assert_eq!(re.find_first_line(text), Some((2, "an error", (6, 11))));
+    /// # }
+    /// ```
+    pub fn find_first_line<'t>(
+        &self,
+        text: &'t str,
+    ) -> Option<(usize, &'t str, (usize, usize))> {
+        self.find(text).map(|(s, e)| {
+            let line = 1 + text[..s].bytes().filter(|&b| b == b'\n').count();
+            let start = text[..s].rfind('\n').map_or(0, |i| i + 1);
+            let end = text[s..].find('\n').map_or(text.len(), |i| s + i);
+            (line, &text[start..end], (s, e))
+        })
+    }
+
+    /// Returns an iterator over every line in `text` that contains a match,
+    /// along with its line number.
+    ///
+    /// Lines are separated by `\n` and numbered from 1. The lines don't
+    /// include their `\n`, and a trailing `\n` doesn't start a new line.
+    /// Each line is searched on its own, so `^` and `$` match at the start
+    /// and end of every line.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"^warn|^error").unwrap();
+    /// let log = "info: up\nwarn: disk\nerror: down\n";
+    /// let lines: Vec<_> = re.grep(log).collect();
+    /// assert_eq!(lines, vec![(2, "warn: disk"), (3, "error: down")]);
+    /// # }
+    /// ```
+    pub fn grep<'r, 't>(&'r self, text: &'t str) -> GrepLines<'r, 't> {
+        GrepLines {
+            re: self,
+            lines: NumberedLines::new(text),
+        }
+    }
+
+    /// Returns an iterator over every line in `text` that contains a match,
+    /// along with its line number and the start and end byte indices of the
+    /// first match on the line.
+    ///
+    /// Lines are split and numbered like in `grep`. The match indices are
+    /// with respect to the line, which makes it easy to highlight the match.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let text = "a 1 22\nb\nc 333";
+    /// let lines: Vec<_> = re.grep_with_match(text).collect();
+    /// assert_eq!(lines, vec![(1, "a 1 22", (2, 3)), (3, "c 333", (2, 5))]);
+    /// # }
+    /// ```
+    pub fn grep_with_match<'r, 't>(
+        &'r self,
+        text: &'t str,
+    ) -> GrepMatches<'r, 't> {
+        GrepMatches {
+            re: self,
+            lines: NumberedLines::new(text),
+        }
+    }
+
+    /// Returns an iterator over every line in `text` that contains a match,
+    /// along with its line number and the capture groups of the first match
+    /// on the line.
+    ///
+    /// Lines are split and numbered like in `grep`. The captures are taken
+    /// from the line, so their positions are with respect to the line.
+    ///
+    /// # Example
+    ///
+    /// Extract the fields of log lines that report an error:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"^(?P<level>ERROR) (?P<msg>.+)$").unwrap();
+    /// let log = "INFO started\nERROR disk full\nINFO stopped";
+    /// for (n, _, caps) in re.grep_captures(log) {
+    ///     assert_eq!(n, 2);
+    ///     assert_eq!(caps.name("msg"), Some("disk full"));
+    /// }
+    /// # }
+    /// ```
+    pub fn grep_captures<'r, 't>(
+        &'r self,
+        text: &'t str,
+    ) -> GrepCaptures<'r, 't> {
+        GrepCaptures {
+            re: self,
+            lines: NumberedLines::new(text),
+        }
+    }
+
+    /// Returns an iterator over every line in `text` that doesn't contain a
+    /// match, along with its line number.
+    ///
+    /// This is the opposite of `grep`, like `grep -v`. Lines are split and
+    /// numbered like in `grep`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"^#").unwrap();
+    /// let config = "# comment\nkey = 1\n# another\nother = 2";
+    /// let lines: Vec<_> = re.grep_inverse(config).collect();
+    /// assert_eq!(lines, vec![(2, "key = 1"), (4, "other = 2")]);
+    /// # }
+    /// ```
+    pub fn grep_inverse<'r, 't>(
+        &'r self,
+        text: &'t str,
+    ) -> GrepInverseLines<'r, 't> {
+        GrepInverseLines {
+            re: self,
+            lines: NumberedLines::new(text),
+        }
+    }
+
+    /// Returns the number of lines in `text` that contain a match.
+    ///
+    /// Lines are split like in `grep`. This is the same as
+    /// `grep(text).count()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"error").unwrap();
+    /// assert_eq!(re.grep_count("error\nok\nerror error\n"), 2);
+    /// # }
+    /// ```
+    pub fn grep_count(&self, text: &str) -> usize {
+        NumberedLines::new(text)
+            .filter(|&(_, line)| self.is_match(line))
+            .count()
+    }
+
+    /// Returns an iterator over every line that contains a match in each of
+    /// the given files.
+    ///
+    /// Each matching line is yielded as a `GrepFileItem::Match` with the path
+    /// of its file, its line number, the line itself and the start and end
+    /// byte indices of its first match, like in `grep_with_match`. Files are
+    /// read one at a time, only once the lines of the previous file have been
+    /// yielded. If a file can't be read (including when it isn't valid
+    /// UTF-8), a `GrepFileItem::GrepError` is yielded for it instead and the
+    /// search continues with the next file.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate regex; use regex::{GrepFileItem, Regex};
+    /// # fn main() {
+    /// let re = Regex::new(r"TODO").unwrap();
+    /// for item in re.grep_files(&["src/lib.rs", "src/main.rs"]) {
+    ///     match item {
+    ///         GrepFileItem::Match(path, n, line, _) => {
+    ///             println!("{}:{}: {}", path, n, line);
+    ///         }
+    ///         GrepFileItem::GrepError(path, err) => {
+    ///             println!("{}: {}", path, err);
+    ///         }
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    pub fn grep_files<'r, 'f, P: AsRef<Path>>(
+        &'r self,
+        files: &'f [P],
+    ) -> GrepFiles<'r, 'f, P> {
+        GrepFiles {
+            re: self,
+            files: files.iter(),
+            pending: vec![].into_iter(),
+        }
+    }
+
+    /// Returns a copy of `text` with every successive non-overlapping match
+    /// wrapped in `open_tag` and `close_tag`.
+    ///
+    /// Matches that immediately follow each other are wrapped together, so
+    /// no tags are inserted between them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"\w+").unwrap();
+    /// assert_eq!(re.highlight_matches("hello world", "<b>", "</b>"),
+    ///            "<b>hello</b> <b>world</b>");
+    /// # }
+    /// ```
+    pub fn highlight_matches(
         &self,
         text: &str,
-        limit: usize,
-        mut rep: R,
+        open_tag: &str,
+        close_tag: &str,
     ) -> String {
-
-        // If we know that the replacement doesn't have any capture expansions,
-        // then we can fast path. The fast path can make a tremendous
-        // difference:
-        //
-        //   1) We use `find_iter` instead of `captures_iter`. Not asking for
-        //      captures generally makes the regex engines faster.
-        //   2) We don't need to look up all of the capture groups and do
-        //      replacements inside the replacement string. We just push it
-        //      at each match and be done with it.
-        if let Some(rep) = rep.no_expand() {
-            let mut new = String::with_capacity(text.len());
-            let mut last_match = 0;
-            for (i, (s, e)) in self.find_iter(text).enumerate() {
-                if limit > 0 && i >= limit {
-                    break
+        let mut out = String::with_capacity(text.len());
+        let mut last_end = 0;
+        let mut in_tag = false;
+        for (s, e) in self.find_iter(text) {
+            if !in_tag || s != last_end {
+                if in_tag {
+                    out.push_str(close_tag);
                 }
-                new.push_str(&text[last_match..s]);
-                new.push_str(&rep);
-                last_match = e;
+                out.push_str(&text[last_end..s]);
+                out.push_str(open_tag);
+                in_tag = true;
             }
-            new.push_str(&text[last_match..]);
-            return new;
+            out.push_str(&text[s..e]);
+            last_end = e;
         }
+        if in_tag {
+            out.push_str(close_tag);
+        }
+        out.push_str(&text[last_end..]);
+        out
+    }
+
+    /// Returns a copy of `text` with the capture groups of every successive
+    /// non-overlapping match wrapped in tags.
+    ///
+    /// `tags` is a list of `(group_index, open_tag, close_tag)` triples, so
+    /// every group can be given its own tags. Groups that didn't participate
+    /// in a match, or that don't exist, are left alone. When groups are
+    /// nested, so are their tags.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(\w+)=(\d+)").unwrap();
+    /// let tags = [(1, "<k>", "</k>"), (2, "<v>", "</v>")];
+    /// assert_eq!(re.highlight_groups("a=1, b=2", &tags),
+    ///            "<k>a</k>=<v>1</v>, <k>b</k>=<v>2</v>");
+    /// # }
+    /// ```
+    pub fn highlight_groups(
+        &self,
+        text: &str,
+        tags: &[(usize, &str, &str)],
+    ) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last_end = 0;
+        for caps in self.captures_iter(text) {
+            // Tags are sorted by their position, with closing tags before
+            // opening ones. Among groups with the same span, the one with the
+            // lower index contains the others, so it's opened first and
+            // closed last.
+            let mut events = vec![];
+            for &(i, open_tag, close_tag) in tags {
+                let (s, e) = match caps.pos(i) {
+                    None => continue,
+                    Some(pos) => pos,
+                };
+                if s == e {
+                    events.push(((s, 1, !e, i), open_tag, close_tag));
+                } else {
+                    events.push(((s, 1, !e, i), open_tag, ""));
+                    events.push(((e, 0, !s, !i), close_tag, ""));
+                }
+            }
+            events.sort_by_key(|&(key, _, _)| key);
+            for ((pos, _, _, _), tag1, tag2) in events {
+                out.push_str(&text[last_end..pos]);
+                out.push_str(tag1);
+                out.push_str(tag2);
+                last_end = pos;
+            }
+        }
+        out.push_str(&text[last_end..]);
+        out
+    }
+
+    /// Returns the start and end byte indices of every successive
+    /// non-overlapping match in each of the given texts.
+    ///
+    /// The result has one `Vec` of matches for each text, in the same order
+    /// as `texts`. This is equivalent to calling `find_iter` on each text,
+    /// except the matching engine's cache is fetched only once for the whole
+    /// batch.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let matches = re.batch_find_all(&["a1b22", "none", "333"]);
+    /// assert_eq!(matches, vec![
+    ///     vec![(1, 2), (3, 5)],
+    ///     vec![],
+    ///     vec![(0, 3)],
+    /// ]);
+    /// # }
+    /// ```
+    pub fn batch_find_all(&self, texts: &[&str]) -> Vec<Vec<(usize, usize)>> {
+        match self.0 {
+            _Regex::Dynamic(ref exec) => {
+                let searcher = exec.searcher_str();
+                texts.iter()
+                     .map(|text| searcher.clone().find_iter(text).collect())
+                     .collect()
+            }
+            _Regex::Plugin(ref plug) => {
+                texts.iter()
+                     .map(|text| plug.find_iter(text).collect())
+                     .collect()
+            }
+        }
+    }
+
+    /// Calls `f` with `state` and the start and end byte indices of each
+    /// successive non-overlapping match in `text`. `state` is returned once
+    /// all matches have been visited.
+    ///
+    /// # Example
+    ///
+    /// Sum the lengths of all numbers in some text:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let mut total = 0;
+    /// re.find_with_state("a1 b22 c333", &mut total, |total, (s, e)| {
+    ///     *total += e - s;
+    /// });
+    /// assert_eq!(total, 6);
+    /// # }
+    /// ```
+    pub fn find_with_state<'s, S, F>(
+        &self,
+        text: &str,
+        state: &'s mut S,
+        mut f: F,
+    ) -> &'s mut S where F: FnMut(&mut S, (usize, usize)) {
+        for pos in self.find_iter(text) {
+            f(state, pos);
+        }
+        state
+    }
+
+    /// Like `find_with_state`, except `f` may fail. Searching stops at the
+    /// first error returned by `f`, which is then returned.
+    ///
+    /// # Example
+    ///
+    /// Parse every number in some text, stopping at the first one that
+    /// overflows a `u8`:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let text = "1 2 300 4";
+    /// let mut nums: Vec<u8> = vec![];
+    /// let res = re.try_find_with_state(text, &mut nums, |nums, (s, e)| {
+    ///     nums.push(try!(text[s..e].parse()));
+    ///     Ok::<(), std::num::ParseIntError>(())
+    /// });
+    /// assert!(res.is_err());
+    /// assert_eq!(nums, vec![1, 2]);
+    /// # }
+    /// ```
+    pub fn try_find_with_state<S, E, F>(
+        &self,
+        text: &str,
+        state: &mut S,
+        mut f: F,
+    ) -> Result<(), E>
+            where F: FnMut(&mut S, (usize, usize)) -> Result<(), E> {
+        for pos in self.find_iter(text) {
+            try!(f(state, pos));
+        }
+        Ok(())
+    }
+
+    /// Returns the capture groups corresponding to the leftmost-first
+    /// match in `text`. Capture group `0` always corresponds to the entire
+    /// match. If no match is found, then `None` is returned.
+    ///
+    /// You should only use `captures` if you need access to submatches.
+    /// Otherwise, `find` is faster for discovering the location of the overall
+    /// match.
+    ///
+    /// # Examples
+    ///
+    /// Say you have some text with movie names and their release years,
+    /// like "'Citizen Kane' (1941)". It'd be nice if we could search for text
+    /// looking like that, while also extracting the movie name and its release
+    /// year separately.
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"'([^']+)'\s+\((\d{4})\)").unwrap();
+    /// let text = "Not my favorite movie: 'Citizen Kane' (1941).";
+    /// let caps = re.captures(text).unwrap();
+    /// assert_eq!(caps.at(1), Some("Citizen Kane"));
+    /// assert_eq!(caps.at(2), Some("1941"));
+    /// assert_eq!(caps.at(0), Some("'Citizen Kane' (1941)"));
+    /// // You can also access the groups by index using the Index notation.
+    /// // Note that this will panic on an invalid index.
+    /// assert_eq!(&caps[1], "Citizen Kane");
+    /// assert_eq!(&caps[2], "1941");
+    /// assert_eq!(&caps[0], "'Citizen Kane' (1941)");
+    /// # }
+    /// ```
+    ///
+    /// Note that the full match is at capture group `0`. Each subsequent
+    /// capture group is indexed by the order of its opening `(`.
+    ///
+    /// We can make this example a bit clearer by using *named* capture groups:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"'(?P<title>[^']+)'\s+\((?P<year>\d{4})\)")
+    ///                .unwrap();
+    /// let text = "Not my favorite movie: 'Citizen Kane' (1941).";
+    /// let caps = re.captures(text).unwrap();
+    /// assert_eq!(caps.name("title"), Some("Citizen Kane"));
+    /// assert_eq!(caps.name("year"), Some("1941"));
+    /// assert_eq!(caps.at(0), Some("'Citizen Kane' (1941)"));
+    /// // You can also access the groups by name using the Index notation.
+    /// // Note that this will panic on an invalid group name.
+    /// assert_eq!(&caps["title"], "Citizen Kane");
+    /// assert_eq!(&caps["year"], "1941");
+    /// assert_eq!(&caps[0], "'Citizen Kane' (1941)");
+    ///
+    /// # }
+    /// ```
+    ///
+    /// Here we name the capture groups, which we can access with the `name`
+    /// method or the `Index` notation with a `&str`. Note that the named
+    /// capture groups are still accessible with `at` or the `Index` notation
+    /// with a `usize`.
+    ///
+    /// The `0`th capture group is always unnamed, so it must always be
+    /// accessed with `at(0)` or `[0]`.
+    pub fn captures<'t>(&self, text: &'t str) -> Option<Captures<'t>> {
+        let mut slots = vec![None; 2 * self.captures_len()];
+        self.read_captures_at(&mut slots, text, 0).map(|_| Captures {
+            text: text,
+            slots: slots,
+            named_groups: NamedGroups::from_regex(self)
+        })
+    }
+
+    /// Returns the capture groups of the leftmost-first match in
+    /// `text[start..end]`. If no match is found, then `None` is returned.
+    ///
+    /// Like `find_between_positions`, the regex never looks at text outside
+    /// of the window, so the window's bounds are treated as the bounds of the
+    /// text. The positions of the capture groups are relative to the start of
+    /// `text`.
+    ///
+    /// # Panics
+    ///
+    /// If `start..end` is not a valid range of `text` whose bounds lie on
+    /// UTF-8 character boundaries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"([a-z])([0-9])").unwrap();
+    /// let caps = re.captures_between_positions("a1 b2 c3", 1, 8).unwrap();
+    /// assert_eq!(caps.pos(0), Some((3, 5)));
+    /// assert_eq!(caps.at(2), Some("2"));
+    /// # }
+    /// ```
+    pub fn captures_between_positions<'t>(
+        &self,
+        text: &'t str,
+        start: usize,
+        end: usize,
+    ) -> Option<Captures<'t>> {
+        self.captures(&text[start..end])
+            .map(|caps| caps.offset_into(text, start))
+    }
+
+    /// Returns the capture groups of every successive non-overlapping match
+    /// in `text[start..end]`.
+    ///
+    /// Like `captures_between_positions`, the window's bounds are treated as
+    /// the bounds of the text and the positions of the capture groups are
+    /// relative to the start of `text`. Since every window is searched on
+    /// its own, disjoint windows of the same text can be searched in
+    /// parallel.
+    ///
+    /// # Panics
+    ///
+    /// If `start..end` is not a valid range of `text` whose bounds lie on
+    /// UTF-8 character boundaries.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"([a-z])([0-9])").unwrap();
+    /// let all = re.all_captures_between_positions("a1 b2 c3", 1, 8);
+    /// let digits: Vec<_> = all.iter().map(|caps| caps.pos(2)).collect();
+    /// assert_eq!(digits, vec![Some((4, 5)), Some((7, 8))]);
+    /// # }
+    /// ```
+    pub fn all_captures_between_positions<'t>(
+        &self,
+        text: &'t str,
+        start: usize,
+        end: usize,
+    ) -> Vec<Captures<'t>> {
+        self.captures_iter(&text[start..end])
+            .map(|caps| caps.offset_into(text, start))
+            .collect()
+    }
+
+    /// Returns a parallel iterator over the text matched by capture group
+    /// `group` in every successive non-overlapping match in `text`.
+    ///
+    /// An item is `None` if the group didn't participate in its match (or if
+    /// `group` isn't a valid group index).
+    ///
+    /// The locations of the matches are found sequentially, since where one
+    /// match begins depends on where the previous one ended. Only the (more
+    /// expensive) work of resolving capture groups for each match is done in
+    /// parallel. This means splitting the text into chunks is never needed,
+    /// so matches that would straddle a chunk boundary aren't a concern.
+    ///
+    /// This is only available when the `rayon` Cargo feature is enabled,
+    /// which needs a much newer Rust than the rest of this crate.
+    #[cfg(feature = "rayon")]
+    pub fn find_all_groups_parallel<'r, 't>(
+        &'r self,
+        text: &'t str,
+        group: usize,
+    ) -> ParallelGroups<'r, 't> {
+        ParallelGroups {
+            re: self,
+            text: text,
+            group: group,
+            starts: self.find_iter(text).map(|(s, _)| s).collect(),
+        }
+    }
+
+    /// Returns the text matched by the capture group named `name` in the
+    /// leftmost-first match in `text`.
+    ///
+    /// `None` is returned if there is no match, if there is no group named
+    /// `name` or if that group didn't participate in the match. If there is
+    /// no such group, `text` isn't searched at all.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?P<y>\d{4})-(?P<m>\d{2})").unwrap();
+    /// assert_eq!(re.find_first_named_group("2010-03, 2014-10", "m"),
+    ///            Some("03"));
+    /// assert_eq!(re.find_first_named_group("2010-03", "d"), None);
+    /// # }
+    /// ```
+    pub fn find_first_named_group<'t>(
+        &self,
+        text: &'t str,
+        name: &str,
+    ) -> Option<&'t str> {
+        let i = match self.capture_group_by_name(name) {
+            None => return None,
+            Some(i) => i,
+        };
+        let mut slots = vec![None; 2 * self.captures_len()];
+        self.read_captures_at(&mut slots, text, 0);
+        match (slots[2 * i], slots[2 * i + 1]) {
+            (Some(s), Some(e)) => Some(&text[s..e]),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of distinct strings matched by this regex in
+    /// `text`.
+    ///
+    /// Matches are found the same way as `find_iter`, but matches of the
+    /// same text at different positions are only counted once.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"\w+").unwrap();
+    /// assert_eq!(re.count_unique_matches("to be or not to be"), 4);
+    /// # }
+    /// ```
+    pub fn count_unique_matches(&self, text: &str) -> usize {
+        self.find_iter(text)
+            .map(|(s, e)| &text[s..e])
+            .collect::<HashSet<&str>>()
+            .len()
+    }
+
+    /// Returns an iterator over all the non-overlapping capture groups matched
+    /// in `text`. This is operationally the same as `find_iter`, except it
+    /// yields information about submatches.
+    ///
+    /// # Example
+    ///
+    /// We can use this to find all movie titles and their release years in
+    /// some text, where the movie is formatted like "'Title' (xxxx)":
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"'(?P<title>[^']+)'\s+\((?P<year>\d{4})\)")
+    ///                .unwrap();
+    /// let text = "'Citizen Kane' (1941), 'The Wizard of Oz' (1939), 'M' (1931).";
+    /// for caps in re.captures_iter(text) {
+    ///     println!("Movie: {:?}, Released: {:?}",
+    ///              caps.name("title"), caps.name("year"));
+    /// }
+    /// // Output:
+    /// // Movie: Citizen Kane, Released: 1941
+    /// // Movie: The Wizard of Oz, Released: 1939
+    /// // Movie: M, Released: 1931
+    /// # }
+    /// ```
+    pub fn captures_iter<'r, 't>(
+        &'r self,
+        text: &'t str,
+    ) -> FindCaptures<'r, 't> {
+        match self.0 {
+            _Regex::Dynamic(ref exec) => {
+                let it = exec.searcher_str().captures_iter(text);
+                FindCaptures(FindCapturesInner::Dynamic(it))
+            }
+            _Regex::Plugin(ref plug) => {
+                let it = plug.captures_iter(text);
+                FindCaptures(FindCapturesInner::Plugin(it))
+            }
+        }
+    }
+
+    /// Returns an iterator of substrings of `text` delimited by a match of the
+    /// regular expression. Namely, each element of the iterator corresponds to
+    /// text that *isn't* matched by the regular expression.
+    ///
+    /// This method will *not* copy the text given.
+    ///
+    /// # Example
+    ///
+    /// To split a string delimited by arbitrary amounts of spaces or tabs:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[ \t]+").unwrap();
+    /// let fields: Vec<&str> = re.split("a b \t  c\td    e").collect();
+    /// assert_eq!(fields, vec!["a", "b", "c", "d", "e"]);
+    /// # }
+    /// ```
+    pub fn split<'r, 't>(&'r self, text: &'t str) -> RegexSplits<'r, 't> {
+        RegexSplits {
+            finder: self.find_iter(text),
+            last: 0,
+        }
+    }
+
+    /// Returns an iterator of at most `limit` substrings of `text` delimited
+    /// by a match of the regular expression. (A `limit` of `0` will return no
+    /// substrings.) Namely, each element of the iterator corresponds to text
+    /// that *isn't* matched by the regular expression. The remainder of the
+    /// string that is not split will be the last element in the iterator.
+    ///
+    /// This method will *not* copy the text given.
+    ///
+    /// # Example
+    ///
+    /// Get the first two words in some text:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"\W+").unwrap();
+    /// let fields: Vec<&str> = re.splitn("Hey! How are you?", 3).collect();
+    /// assert_eq!(fields, vec!("Hey", "How", "are you?"));
+    /// # }
+    /// ```
+    pub fn splitn<'r, 't>(&'r self, text: &'t str, limit: usize)
+                         -> RegexSplitsN<'r, 't> {
+        RegexSplitsN {
+            splits: self.split(text),
+            n: limit,
+        }
+    }
+
+    /// Returns every non-empty span of `text` that isn't part of a match, in
+    /// order.
+    ///
+    /// This is like `split`, except that empty spans (for example, between
+    /// two adjacent matches or before a match at the start of `text`) are
+    /// left out. It is the text that "passes through" when this regex is
+    /// used as a filter.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"<[^>]*>").unwrap();
+    /// let text = "<p><b>Hello</b>, world!</p>";
+    /// assert_eq!(re.pipe(text), vec!["Hello", ", world!"]);
+    /// # }
+    /// ```
+    pub fn pipe<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        self.split(text).filter(|span| !span.is_empty()).collect()
+    }
+
+    /// Returns an iterator that splits `text` into segments that are matched
+    /// by this regex and segments that aren't.
+    ///
+    /// The segments cover all of `text` exactly once, in order. Matches are
+    /// found the same way as `find_iter`. Unmatched segments are never
+    /// empty, so two adjacent matches produce two consecutive `Matched`
+    /// segments.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::{Regex, TextSegment};
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let segments: Vec<_> = re.partition_text("a1b22").collect();
+    /// assert_eq!(segments, vec![
+    ///     TextSegment::Unmatched("a"),
+    ///     TextSegment::Matched("1"),
+    ///     TextSegment::Unmatched("b"),
+    ///     TextSegment::Matched("22"),
+    /// ]);
+    /// # }
+    /// ```
+    pub fn partition_text<'r, 't>(
+        &'r self,
+        text: &'t str,
+    ) -> TextSegments<'r, 't> {
+        TextSegments {
+            finder: self.find_iter(text),
+            last: 0,
+            next_match: None,
+        }
+    }
+
+    /// Returns an iterator that splits `text` into tokens that are matched by
+    /// this regex and tokens that aren't.
+    ///
+    /// This is like `partition_text`, so the tokens cover all of `text`
+    /// without gaps, but matched tokens also come with the start and end
+    /// byte indices of their match.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::{Regex, Token};
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let tokens: Vec<_> = re.tokenize("a1b22").collect();
+    /// assert_eq!(tokens, vec![
+    ///     Token::Unmatched("a"),
+    ///     Token::Matched("1", (1, 2)),
+    ///     Token::Unmatched("b"),
+    ///     Token::Matched("22", (3, 5)),
+    /// ]);
+    /// # }
+    /// ```
+    pub fn tokenize<'r, 't>(&'r self, text: &'t str) -> Tokens<'r, 't> {
+        Tokens {
+            segments: self.partition_text(text),
+            pos: 0,
+        }
+    }
+
+    /// Replaces the leftmost-first match with the replacement provided.
+    /// The replacement can be a regular string (where `$N` and `$name` are
+    /// expanded to match capture groups) or a function that takes the matches'
+    /// `Captures` and returns the replaced string.
+    ///
+    /// If no match is found, then a copy of the string is returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Note that this function is polymorphic with respect to the replacement.
+    /// In typical usage, this can just be a normal string:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new("[^01]+").unwrap();
+    /// assert_eq!(re.replace("1078910", ""), "1010");
+    /// # }
+    /// ```
+    ///
+    /// But anything satisfying the `Replacer` trait will work. For example,
+    /// a closure of type `|&Captures| -> String` provides direct access to the
+    /// captures corresponding to a match. This allows one to access
+    /// submatches easily:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # use regex::Captures; fn main() {
+    /// let re = Regex::new(r"([^,\s]+),\s+(\S+)").unwrap();
+    /// let result = re.replace("Springsteen, Bruce", |caps: &Captures| {
+    ///     format!("{} {}", caps.at(2).unwrap_or(""), caps.at(1).unwrap_or(""))
+    /// });
+    /// assert_eq!(result, "Bruce Springsteen");
+    /// # }
+    /// ```
+    ///
+    /// But this is a bit cumbersome to use all the time. Instead, a simple
+    /// syntax is supported that expands `$name` into the corresponding capture
+    /// group. Here's the last example, but using this expansion technique
+    /// with named capture groups:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?P<last>[^,\s]+),\s+(?P<first>\S+)").unwrap();
+    /// let result = re.replace("Springsteen, Bruce", "$first $last");
+    /// assert_eq!(result, "Bruce Springsteen");
+    /// # }
+    /// ```
+    ///
+    /// Note that using `$2` instead of `$first` or `$1` instead of `$last`
+    /// would produce the same result. To write a literal `$` use `$$`.
+    ///
+    /// Finally, sometimes you just want to replace a literal string with no
+    /// submatch expansion. This can be done by wrapping a string with
+    /// `NoExpand`:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// use regex::NoExpand;
+    ///
+    /// let re = Regex::new(r"(?P<last>[^,\s]+),\s+(\S+)").unwrap();
+    /// let result = re.replace("Springsteen, Bruce", NoExpand("$2 $last"));
+    /// assert_eq!(result, "$2 $last");
+    /// # }
+    /// ```
+    pub fn replace<R: Replacer>(&self, text: &str, rep: R) -> String {
+        self.replacen(text, 1, rep)
+    }
+
+    /// Replaces all non-overlapping matches in `text` with the replacement
+    /// provided. This is the same as calling `replacen` with `limit` set to
+    /// `0`.
+    ///
+    /// See the documentation for `replace` for details on how to access
+    /// submatches in the replacement string.
+    pub fn replace_all<R: Replacer>(&self, text: &str, rep: R) -> String {
+        self.replacen(text, 0, rep)
+    }
+
+    /// Replaces at most `limit` non-overlapping matches in `text` with the
+    /// replacement provided. If `limit` is 0, then all non-overlapping matches
+    /// are replaced.
+    ///
+    /// See the documentation for `replace` for details on how to access
+    /// submatches in the replacement string.
+    pub fn replacen<R: Replacer>(
+        &self,
+        text: &str,
+        limit: usize,
+        mut rep: R,
+    ) -> String {
+
+        // If we know that the replacement doesn't have any capture expansions,
+        // then we can fast path. The fast path can make a tremendous
+        // difference:
+        //
+        //   1) We use `find_iter` instead of `captures_iter`. Not asking for
+        //      captures generally makes the regex engines faster.
+        //   2) We don't need to look up all of the capture groups and do
+        //      replacements inside the replacement string. We just push it
+        //      at each match and be done with it.
+        if let Some(rep) = rep.no_expand() {
+            let mut new = String::with_capacity(text.len());
+            let mut last_match = 0;
+            for (i, (s, e)) in self.find_iter(text).enumerate() {
+                if limit > 0 && i >= limit {
+                    break
+                }
+                new.push_str(&text[last_match..s]);
+                new.push_str(&rep);
+                last_match = e;
+            }
+            new.push_str(&text[last_match..]);
+            return new;
+        }
+
+        // The slower path, which we use if the replacement needs access to
+        // capture groups.
+        let mut new = String::with_capacity(text.len());
+        let mut last_match = 0;
+        for (i, cap) in self.captures_iter(text).enumerate() {
+            if limit > 0 && i >= limit {
+                break
+            }
+            // unwrap on 0 is OK because captures only reports matches
+            let (s, e) = cap.pos(0).unwrap();
+            new.push_str(&text[last_match..s]);
+            new.push_str(&rep.reg_replace(&cap));
+            last_match = e;
+        }
+        new.push_str(&text[last_match..]);
+        new
+    }
+
+    /// Replaces only the `n`th non-overlapping match in `text` (counting from
+    /// `0`) with the replacement provided. If there are `n` or fewer matches,
+    /// then `text` is returned unchanged.
+    ///
+    /// See the documentation for `replace` for details on how to access
+    /// submatches in the replacement string.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?P<n>[0-9]+)").unwrap();
+    /// assert_eq!(re.replace_nth("1, 2, 3", 1, "[$n]"), "1, [2], 3");
+    /// # }
+    /// ```
+    pub fn replace_nth<R: Replacer>(
+        &self,
+        text: &str,
+        n: usize,
+        mut rep: R,
+    ) -> String {
+        // See `replacen` for why replacements without capture expansions
+        // are handled separately.
+        let (s, e, replacement) = if let Some(rep) = rep.no_expand() {
+            match self.find_iter(text).nth(n) {
+                None => return text.to_owned(),
+                Some((s, e)) => (s, e, rep.into_owned()),
+            }
+        } else {
+            match self.captures_iter(text).nth(n) {
+                None => return text.to_owned(),
+                Some(cap) => {
+                    // unwrap on 0 is OK because captures only reports matches
+                    let (s, e) = cap.pos(0).unwrap();
+                    (s, e, rep.reg_replace(&cap).into_owned())
+                }
+            }
+        };
+        let mut new = String::with_capacity(text.len());
+        new.push_str(&text[..s]);
+        new.push_str(&replacement);
+        new.push_str(&text[e..]);
+        new
+    }
+
+    /// Returns the end location of a match in the text given.
+    ///
+    /// This method may have the same performance characteristics as
+    /// `is_match`, except it provides an end location for a match. In
+    /// particular, the location returned *may be shorter* than the proper end
+    /// of the leftmost-first match.
+    ///
+    /// # Example
+    ///
+    /// Typically, `a+` would match the entire first sequence of `a` in some
+    /// text, but `shortest_match` can give up as soon as it sees the first
+    /// `a`.
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let text = "aaaaa";
+    /// let pos = Regex::new(r"a+").unwrap().shortest_match(text);
+    /// assert_eq!(pos, Some(1));
+    /// # }
+    /// ```
+    pub fn shortest_match(&self, text: &str) -> Option<usize> {
+        match self.0 {
+            _Regex::Dynamic(ref exec) => {
+                exec.searcher_str().shortest_match_at(text, 0)
+            }
+            _Regex::Plugin(ref plug) => plug.shortest_match_at(text, 0),
+        }
+    }
+
+    /// Computes the complete DFA for this regex and returns its transition
+    /// table.
+    ///
+    /// The table can be embedded in other systems to find the end of the
+    /// leftmost-first match without this crate. Its format is described on
+    /// the `DfaTable` type.
+    ///
+    /// An error is returned if the regex contains a Unicode word boundary
+    /// (`\b` or `\B` without disabling the `u` flag) or if the DFA has too
+    /// many states.
+    ///
+    /// # Example
+    ///
+    /// Find the end of the first number in some text by running the table
+    /// by hand:
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let table = Regex::new(r"[0-9]+").unwrap().compile_to_dfa().unwrap();
+    /// let text = b"ab123c45";
+    /// let eoi = table.transitions[0].len() - 1;
+    /// let (mut s, mut last_match) = (1, None);
+    /// for (i, &b) in text.iter().enumerate() {
+    ///     let cls = table.byte_classes[b as usize] as usize;
+    ///     s = table.transitions[s][cls] as usize;
+    ///     if table.matches[s] { last_match = Some(i); }
+    ///     if s == 0 { break; }
+    /// }
+    /// if s != 0 && table.matches[table.transitions[s][eoi] as usize] {
+    ///     last_match = Some(text.len());
+    /// }
+    /// assert_eq!(last_match, Some(5));
+    /// # }
+    /// ```
+    pub fn compile_to_dfa(&self) -> Result<DfaTable, Error> {
+        try!(self.to_exec()).dfa_table()
+    }
+
+    /// Returns true if this regex can be matched by following at most one
+    /// path through its NFA at a time.
+    ///
+    /// This is a static check: at every point where the NFA can take one of
+    /// two paths, the characters that each path can consume next must be
+    /// disjoint, and at most one path may finish a match without consuming
+    /// more input. Empty width assertions (like `^` or `\b`) are
+    /// conservatively assumed to always match.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// assert!(Regex::new(r"a+b|c").unwrap().is_deterministic());
+    /// assert!(!Regex::new(r"a+ab").unwrap().is_deterministic());
+    /// # }
+    /// ```
+    pub fn is_deterministic(&self) -> bool {
+        self.to_exec().map(|exec| exec.is_deterministic()).unwrap_or(false)
+    }
+
+    /// Returns a static classification of the worst case time it takes to
+    /// search text with this regex.
+    ///
+    /// All regexes can be searched in linear time. Regexes that are anchored
+    /// at the start of the text and have no unbounded repetitions (like `*`
+    /// or `+`) take time bounded by the size of the regex.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::{ComplexityClass, Regex};
+    /// # fn main() {
+    /// let re = Regex::new(r"^[0-9]{4}").unwrap();
+    /// assert_eq!(re.complexity_class(), ComplexityClass::O1);
+    /// let re = Regex::new(r"^[0-9]+").unwrap();
+    /// assert_eq!(re.complexity_class(), ComplexityClass::On);
+    /// # }
+    /// ```
+    pub fn complexity_class(&self) -> ComplexityClass {
+        self.to_exec()
+            .map(|exec| exec.complexity_class())
+            .unwrap_or(ComplexityClass::On)
+    }
+
+    /// Returns the largest number of NFA threads that were alive at the same
+    /// time while searching `text` for the leftmost-first match.
+    ///
+    /// The NFA simulation keeps one thread for every state the regex could
+    /// be in, so the number of threads bounds the work done for each
+    /// character of the text and the memory needed to track captures. A
+    /// regex with a smaller peak on typical input is generally faster. The
+    /// peak is never larger than the number of instructions in the compiled
+    /// program. Text that is skipped by searching for a literal prefix of the
+    /// regex doesn't start any threads.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let loose = Regex::new(r"\w*\w*\w*x").unwrap();
+    /// let tight = Regex::new(r"\w*x").unwrap();
+    /// let text = "aaaaaaaaaaaaaaaax";
+    /// assert!(loose.max_thread_count(text) > tight.max_thread_count(text));
+    /// # }
+    /// ```
+    pub fn max_thread_count(&self, text: &str) -> usize {
+        // Plugin regexes are validated at compile time, so recompiling one
+        // can't fail.
+        self.to_exec().unwrap().max_thread_count(text.as_bytes())
+    }
+
+    /// Returns the average number of bytes of `text` covered by each
+    /// instruction executed by the NFA while searching `text` for the
+    /// leftmost-first match.
+    ///
+    /// This is the number of bytes the NFA looked at divided by the number
+    /// of instructions it executed. Higher is better: a regex that needs
+    /// fewer instructions per byte of text searches faster. If no
+    /// instructions were executed (for example, because a literal prefix of
+    /// the regex isn't in `text`), then `0.0` is returned.
+    ///
+    /// Counting instructions slows down every search, so this is only
+    /// available when the `instrument` Cargo feature is enabled.
+    #[cfg(feature = "instrument")]
+    pub fn benchmark_instruction_efficiency(&self, text: &str) -> f64 {
+        // Plugin regexes are validated at compile time, so recompiling one
+        // can't fail.
+        let exec = self.to_exec().unwrap();
+        let (insts, bytes) = exec.instruction_count(text.as_bytes());
+        if insts == 0 {
+            0.0
+        } else {
+            bytes as f64 / insts as f64
+        }
+    }
+
+    /// Returns an English description of the program used to match this
+    /// regex.
+    ///
+    /// Each line of the description explains one instruction of the
+    /// compiled program, such as matching a character or starting a capture
+    /// group. Instructions run in order unless the description says to go to
+    /// another instruction. The exact wording is meant for people and may
+    /// change between releases.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"a[A-Z]").unwrap();
+    /// assert_eq!(re.explain(), "\
+    /// 0: Starts capture group 0.
+    /// 1: Matches the literal character 'a'.
+    /// 2: Matches any character from 'A' to 'Z'.
+    /// 3: Ends capture group 0.
+    /// 4: Reports a match.
+    /// ");
+    /// # }
+    /// ```
+    pub fn explain(&self) -> String {
+        // Plugin regexes are validated at compile time, so recompiling one
+        // can't fail.
+        self.to_exec().unwrap().explain()
+    }
+
+    /// Returns an SVG diagram of the NFA used to match this regex.
+    ///
+    /// Every state is drawn as a circle labeled with its index, with match
+    /// states drawn as double circles. Transitions that consume input are
+    /// solid arrows labeled with the characters they match. Epsilon
+    /// transitions (for capture groups, alternations, repetitions and empty
+    /// width assertions) are dashed arrows.
+    ///
+    /// This is only available when the `diagram` Cargo feature is enabled.
+    #[cfg(feature = "diagram")]
+    pub fn to_automaton_diagram_svg(&self) -> String {
+        // Plugin regexes are validated at compile time, so recompiling one
+        // can't fail.
+        self.to_exec().unwrap().diagram_svg()
+    }
+
+    /// Returns the original string of this regex.
+    pub fn as_str(&self) -> &str {
+        match self.0 {
+            _Regex::Dynamic(ref exec) => &exec.regex_strings()[0],
+            _Regex::Plugin(ref plug) => &plug.original,
+        }
+    }
+
+    /// Returns Rust source code for an expression that builds an equivalent
+    /// regex.
+    ///
+    /// The pattern is written as a raw string literal with as many `#`s as
+    /// it needs, so the generated code can be pasted into a source file
+    /// as is.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r#"\w+"#).unwrap();
+    /// assert_eq!(re.to_rust_const_expr(),
+    ///            r###"Regex::new(r#"\w+"#).unwrap()"###);
+    /// # }
+    /// ```
+    pub fn to_rust_const_expr(&self) -> String {
+        let pattern = self.as_str();
+        let mut hashes = String::from("#");
+        while pattern.contains(&format!("\"{}", hashes)) {
+            hashes.push('#');
+        }
+        format!("Regex::new(r{}\"{}\"{}).unwrap()", hashes, pattern, hashes)
+    }
+
+    /// Returns an iterator over the capture names.
+    pub fn capture_names(&self) -> CaptureNames {
+        CaptureNames(match self.0 {
+            _Regex::Plugin(ref n) => _CaptureNames::Plugin(n.names.iter()),
+            _Regex::Dynamic(ref d) => {
+                _CaptureNames::Dynamic(d.capture_names().iter())
+            }
+        })
+    }
+
+    /// Returns an iterator over the index and name of every named capture
+    /// group, in order.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?P<y>\d{4})-(\d{2})-(?P<d>\d{2})").unwrap();
+    /// let names: Vec<_> = re.group_names().collect();
+    /// assert_eq!(names, vec![(1, "y"), (3, "d")]);
+    /// # }
+    /// ```
+    pub fn group_names<'r>(&'r self) -> GroupNames<'r> {
+        GroupNames(self.capture_names().enumerate())
+    }
+
+    /// Returns the index of the capture group named `name`, or `None` if
+    /// there is no such group.
+    ///
+    /// This doesn't search any text, so it's a cheap way to check that a
+    /// group exists before looking it up with `Captures::name`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?P<y>\d{4})-(\d{2})-(?P<d>\d{2})").unwrap();
+    /// assert_eq!(re.capture_group_by_name("d"), Some(3));
+    /// assert_eq!(re.capture_group_by_name("m"), None);
+    /// # }
+    /// ```
+    pub fn capture_group_by_name(&self, name: &str) -> Option<usize> {
+        NamedGroups::from_regex(self).pos(name)
+    }
+
+    /// Returns true if this regex has at least one named capture group.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// assert!(Regex::new(r"(?P<y>\d{4})").unwrap().has_named_groups());
+    /// assert!(!Regex::new(r"(\d{4})").unwrap().has_named_groups());
+    /// # }
+    /// ```
+    pub fn has_named_groups(&self) -> bool {
+        self.capture_names().any(|name| name.is_some())
+    }
+
+    /// Returns the number of captures.
+    pub fn captures_len(&self) -> usize {
+        match self.0 {
+            _Regex::Plugin(ref n) => n.names.len(),
+            _Regex::Dynamic(ref d) => d.capture_names().len()
+        }
+    }
+
+    /// Returns the number of capture slots, which is two (a start and an end
+    /// position) for each capture, including the implicit capture of the
+    /// entire match.
+    pub fn capture_slot_count(&self) -> usize {
+        self.captures_len() * 2
+    }
+
+    /// Returns a new regex that matches this regex followed immediately by
+    /// `other`.
+    ///
+    /// Capture groups in `other` are renumbered to follow the capture groups
+    /// in this regex. An error is returned if both regexes use the same
+    /// capture group name or if the combined regex is too big. The new regex
+    /// is compiled with this regex's options, such as its size limit.
+    ///
+    /// The pattern of the new regex (as returned by `as_str`) is built from a
+    /// normalized form of both patterns, so it may not be identical to the
+    /// original patterns.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let word = Regex::new(r"(\w+)").unwrap();
+    /// let num = Regex::new(r"([0-9]+)").unwrap();
+    /// let re = word.chain(&Regex::new("-").unwrap()).unwrap()
+    ///              .chain(&num).unwrap();
+    /// let caps = re.captures("id: abc-123").unwrap();
+    /// assert_eq!(caps.at(1), Some("abc"));
+    /// assert_eq!(caps.at(2), Some("123"));
+    /// # }
+    /// ```
+    pub fn chain(&self, other: &Regex) -> Result<Regex, Error> {
+        let mut re = String::new();
+        if let Some(expr) = try!(self.embeddable()) {
+            re.push_str(&expr);
+        }
+        if let Some(expr) = try!(other.embeddable()) {
+            re.push_str(&expr);
+        }
+        self.rebuild(&re)
+    }
+
+    /// Returns a new regex that matches either this regex or `other`,
+    /// preferring this regex.
+    ///
+    /// This is equivalent to compiling `(?:a)|(?:b)`, where `a` is this
+    /// regex and `b` is `other`. Capture groups in `other` are renumbered to
+    /// follow the capture groups in this regex. An error is returned if both
+    /// regexes use the same capture group name or if the combined regex is
+    /// too big. The new regex is compiled with this regex's options, such as
+    /// its size limit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new("cat").unwrap()
+    ///                .alternate(&Regex::new("dog").unwrap()).unwrap();
+    /// assert_eq!(re.find_iter("cat dog cow").count(), 2);
+    /// # }
+    /// ```
+    pub fn alternate(&self, other: &Regex) -> Result<Regex, Error> {
+        let re = match (try!(self.embeddable()), try!(other.embeddable())) {
+            (Some(a), Some(b)) => format!("{}|{}", a, b),
+            // Empty alternations aren't allowed, so use an optional instead,
+            // making sure to respect the preference order.
+            (Some(a), None) => format!("{}?", a),
+            (None, Some(b)) => format!("{}??", b),
+            (None, None) => "".to_owned(),
+        };
+        self.rebuild(&re)
+    }
+
+    /// Returns a new regex that matches this regex repeated at least `min`
+    /// times and at most `max` times. If `max` is `None`, then there is no
+    /// upper bound.
+    ///
+    /// This is equivalent to compiling `(?:re){min,max}`. An error is
+    /// returned if `max` is less than `min` or if the repeated regex is too
+    /// big.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new("ab").unwrap().repeat(2, Some(3)).unwrap();
+    /// assert_eq!(re.find("ab abababab"), Some((3, 9)));
+    /// # }
+    /// ```
+    pub fn repeat(
+        &self,
+        min: usize,
+        max: Option<usize>,
+    ) -> Result<Regex, Error> {
+        match max {
+            Some(max) => self.repeated(&format!("{{{},{}}}", min, max)),
+            None => self.repeated(&format!("{{{},}}", min)),
+        }
+    }
+
+    /// Returns a new regex that matches this regex zero or one times,
+    /// preferring one.
+    ///
+    /// This is equivalent to compiling `(?:re)?`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new("-").unwrap().optional().unwrap()
+    ///                .chain(&Regex::new("[0-9]+").unwrap()).unwrap();
+    /// assert_eq!(re.find("x-42"), Some((1, 4)));
+    /// assert_eq!(re.find("x42"), Some((1, 3)));
+    /// # }
+    /// ```
+    pub fn optional(&self) -> Result<Regex, Error> {
+        self.repeated("?")
+    }
+
+    /// Returns a new regex that matches this regex zero or more times,
+    /// preferring more.
+    ///
+    /// This is equivalent to compiling `(?:re)*`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new("ab").unwrap().star().unwrap();
+    /// assert_eq!(re.find("ababa"), Some((0, 4)));
+    /// assert_eq!(re.find("ba"), Some((0, 0)));
+    /// # }
+    /// ```
+    pub fn star(&self) -> Result<Regex, Error> {
+        self.repeated("*")
+    }
+
+    /// Returns a new regex that matches this regex one or more times,
+    /// preferring more.
+    ///
+    /// This is equivalent to compiling `(?:re)+`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new("ab").unwrap().plus().unwrap();
+    /// assert_eq!(re.find("xababa"), Some((1, 5)));
+    /// assert_eq!(re.find("ba"), None);
+    /// # }
+    /// ```
+    pub fn plus(&self) -> Result<Regex, Error> {
+        self.repeated("+")
+    }
+
+    /// Returns a new regex that wraps this regex in a capture group, along
+    /// with the index of the new group.
+    ///
+    /// If `name` is given, then the new group is named. The new group gets
+    /// the next free index, which is `self.captures_len()`, so every
+    /// existing group keeps its index. Since the group comes first in the
+    /// pattern returned by `as_str`, compiling that pattern again would
+    /// number it `1` instead.
+    ///
+    /// An error is returned if `name` is not a valid group name, if it is
+    /// already used by this regex or if this regex is empty (since empty
+    /// groups are not allowed).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"([a-z]+)@([a-z]+)").unwrap();
+    /// let (re, i) = re.with_capture_group(Some("email")).unwrap();
+    /// assert_eq!(i, 3);
+    /// let caps = re.captures("me: foo@bar").unwrap();
+    /// assert_eq!(caps.at(i), Some("foo@bar"));
+    /// assert_eq!(caps.name("email"), Some("foo@bar"));
+    /// assert_eq!(caps.at(1), Some("foo"));
+    /// assert_eq!(caps.at(2), Some("bar"));
+    /// # }
+    /// ```
+    pub fn with_capture_group(
+        &self,
+        name: Option<&str>,
+    ) -> Result<(Regex, usize), Error> {
+        let index = self.captures_len();
+        let re = try!(self.embeddable()).unwrap_or_else(String::new);
+        let re = match name {
+            None => format!("({})", re),
+            Some(name) => format!("(?P<{}>{})", name, re),
+        };
+        // The new group comes first in the pattern, followed by any groups
+        // that earlier calls wrapped around this regex.
+        let mut outer = vec![index];
+        if let _Regex::Dynamic(ref exec) = self.0 {
+            outer.extend(exec.outer_groups().iter().cloned());
+        }
+        let re = try!(self.builder_for(&re)
+            .outer_groups(outer)
+            .build()
+            .map(Regex::from));
+        Ok((re, index))
+    }
+
+    /// Returns a new regex that matches the same text as this regex, but
+    /// without any capture groups.
+    ///
+    /// Every capture group is turned into a non-capturing group, so the new
+    /// regex only has the implicit group `0` for the overall match. This
+    /// avoids recording submatch positions when only match locations are
+    /// needed.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?P<y>[0-9]{4})-([0-9]{2})").unwrap();
+    /// let re = re.without_captures().unwrap();
+    /// assert_eq!(re.captures_len(), 1);
+    /// assert_eq!(re.find("on 2016-07"), Some((3, 10)));
+    /// # }
+    /// ```
+    pub fn without_captures(&self) -> Result<Regex, Error> {
+        let expr = try!(syntax::ExprBuilder::new().parse(self.as_str()));
+        self.builder_for(&strip_captures(expr).to_string())
+            .outer_groups(vec![])
+            .build()
+            .map(Regex::from)
+    }
 
-        // The slower path, which we use if the replacement needs access to
-        // capture groups.
-        let mut new = String::with_capacity(text.len());
-        let mut last_match = 0;
-        for (i, cap) in self.captures_iter(text).enumerate() {
-            if limit > 0 && i >= limit {
-                break
+    /// Returns a new regex that matches exactly the same text as this regex
+    /// with the same capture groups, but whose pattern is simplified.
+    ///
+    /// The following rules are applied until none of them changes the
+    /// pattern:
+    ///
+    /// * Alternations with a single branch are replaced by that branch, and
+    ///   branches identical to an earlier branch are removed (`a|a` becomes
+    ///   `a`).
+    /// * Nested repetitions of a non-capturing group collapse into one
+    ///   (`(?:a?)+` becomes `a*`).
+    /// * `{1}` repetitions are removed.
+    /// * Nested concatenations and alternations are flattened, and
+    ///   non-capturing groups that aren't needed are removed.
+    ///
+    /// A simpler pattern compiles to a smaller program, which can make
+    /// searching faster. The new regex is compiled with this regex's
+    /// options, such as its size limit.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"(?:(?:a?)+|(?:a?)+)b{1}").unwrap();
+    /// assert_eq!(re.simplify().unwrap().as_str(), "(?u:a)*(?u:b)");
+    /// # }
+    /// ```
+    pub fn simplify(&self) -> Result<Regex, Error> {
+        let mut expr = try!(syntax::ExprBuilder::new().parse(self.as_str()));
+        loop {
+            let next = match simplify_expr(expr.clone()) {
+                syntax::Expr::Group { e, i: None, name: None } => *e,
+                next => next,
+            };
+            if next == expr {
+                break;
             }
-            // unwrap on 0 is OK because captures only reports matches
-            let (s, e) = cap.pos(0).unwrap();
-            new.push_str(&text[last_match..s]);
-            new.push_str(&rep.reg_replace(&cap));
-            last_match = e;
+            expr = next;
         }
-        new.push_str(&text[last_match..]);
-        new
+        self.rebuild(&expr.to_string())
     }
 
-    /// Returns the end location of a match in the text given.
+    /// Returns a new regex that matches the literal string `prefix` followed
+    /// immediately by this regex.
     ///
-    /// This method may have the same performance characteristics as
-    /// `is_match`, except it provides an end location for a match. In
-    /// particular, the location returned *may be shorter* than the proper end
-    /// of the leftmost-first match.
+    /// `prefix` is matched literally; any regex meta characters in it are
+    /// escaped.
     ///
     /// # Example
     ///
-    /// Typically, `a+` would match the entire first sequence of `a` in some
-    /// text, but `shortest_match` can give up as soon as it sees the first
-    /// `a`.
+    /// ```rust
+    /// # extern crate regex; use regex::Regex;
+    /// # fn main() {
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let re = re.with_prefix_literal("$").unwrap();
+    /// assert_eq!(re.find("5 or $10"), Some((5, 8)));
+    /// # }
+    /// ```
+    pub fn with_prefix_literal(&self, prefix: &str) -> Result<Regex, Error> {
+        let re = try!(self.embeddable()).unwrap_or_else(String::new);
+        self.rebuild(&format!("{}{}", quote(prefix), re))
+    }
+
+    /// Returns a new regex that matches this regex followed immediately by
+    /// the literal string `suffix`.
+    ///
+    /// `suffix` is matched literally; any regex meta characters in it are
+    /// escaped.
+    ///
+    /// # Example
     ///
     /// ```rust
     /// # extern crate regex; use regex::Regex;
     /// # fn main() {
-    /// let text = "aaaaa";
-    /// let pos = Regex::new(r"a+").unwrap().shortest_match(text);
-    /// assert_eq!(pos, Some(1));
+    /// let re = Regex::new(r"[0-9]+").unwrap();
+    /// let re = re.with_suffix_literal("%").unwrap();
+    /// assert_eq!(re.find("5 or 10%"), Some((5, 8)));
     /// # }
     /// ```
-    pub fn shortest_match(&self, text: &str) -> Option<usize> {
+    pub fn with_suffix_literal(&self, suffix: &str) -> Result<Regex, Error> {
+        let re = try!(self.embeddable()).unwrap_or_else(String::new);
+        self.rebuild(&format!("{}{}", re, quote(suffix)))
+    }
+
+    /// Returns a new regex that applies the given repetition operator to
+    /// this regex.
+    fn repeated(&self, op: &str) -> Result<Regex, Error> {
+        match try!(self.embeddable()) {
+            // Repeating the empty regex is still the empty regex.
+            None => self.rebuild(""),
+            Some(re) => self.rebuild(&format!("{}{}", re, op)),
+        }
+    }
+
+    /// Finds the leftmost-first match in `text` starting at `start`, filling
+    /// in `slots` with the locations of its capture groups.
+    fn read_captures_at(
+        &self,
+        slots: &mut [Slot],
+        text: &str,
+        start: usize,
+    ) -> Option<(usize, usize)> {
         match self.0 {
             _Regex::Dynamic(ref exec) => {
-                exec.searcher_str().shortest_match_at(text, 0)
+                exec.searcher_str().captures_at(slots, text, start)
             }
-            _Regex::Plugin(ref plug) => plug.shortest_match_at(text, 0),
+            _Regex::Plugin(ref plug) => plug.captures_at(slots, text, start),
         }
     }
 
-    /// Returns the original string of this regex.
-    pub fn as_str(&self) -> &str {
+    /// Returns a builder for `re` that starts out with the options (like the
+    /// size limit) that this regex was compiled with.
+    fn builder_for(&self, re: &str) -> ExecBuilder {
         match self.0 {
-            _Regex::Dynamic(ref exec) => &exec.regex_strings()[0],
-            _Regex::Plugin(ref plug) => &plug.original,
+            _Regex::Dynamic(ref exec) => exec.builder_for(re),
+            _Regex::Plugin(_) => ExecBuilder::new(re),
         }
     }
 
-    /// Returns an iterator over the capture names.
-    pub fn capture_names(&self) -> CaptureNames {
-        CaptureNames(match self.0 {
-            _Regex::Plugin(ref n) => _CaptureNames::Plugin(n.names.iter()),
-            _Regex::Dynamic(ref d) => {
-                _CaptureNames::Dynamic(d.capture_names().iter())
-            }
-        })
+    /// Compiles `re` with the options that this regex was compiled with.
+    fn rebuild(&self, re: &str) -> Result<Regex, Error> {
+        self.builder_for(re).build().map(Regex::from)
     }
 
-    /// Returns the number of captures.
-    pub fn captures_len(&self) -> usize {
+    /// Returns the executor for this regex. Regexes compiled by the `regex!`
+    /// plugin don't have one, so one is built from the original pattern.
+    fn to_exec<'a>(&'a self) -> Result<Cow<'a, Exec>, Error> {
         match self.0 {
-            _Regex::Plugin(ref n) => n.names.len(),
-            _Regex::Dynamic(ref d) => d.capture_names().len()
+            _Regex::Dynamic(ref exec) => Ok(Cow::Borrowed(exec)),
+            _Regex::Plugin(ref plug) => {
+                ExecBuilder::new(plug.original).build().map(Cow::Owned)
+            }
+        }
+    }
+
+    /// Returns this regex's pattern in a normalized form that can be embedded
+    /// in a larger pattern without changing its meaning.
+    ///
+    /// `None` is returned if the pattern is empty, since empty groups like
+    /// `(?:)` are not allowed.
+    fn embeddable(&self) -> Result<Option<String>, Error> {
+        let expr = try!(syntax::ExprBuilder::new().parse(self.as_str()));
+        if let syntax::Expr::Empty = expr {
+            return Ok(None);
         }
+        Ok(Some(format!("(?:{})", expr)))
     }
 }
 
@@ -659,6 +2695,24 @@ impl<'r> Iterator for CaptureNames<'r> {
     }
 }
 
+/// An iterator over the index and name of every named capture group.
+///
+/// `'r` is the lifetime of the compiled regular expression.
+pub struct GroupNames<'r>(iter::Enumerate<CaptureNames<'r>>);
+
+impl<'r> Iterator for GroupNames<'r> {
+    type Item = (usize, &'r str);
+
+    fn next(&mut self) -> Option<(usize, &'r str)> {
+        while let Some((i, name)) = self.0.next() {
+            if let Some(name) = name {
+                return Some((i, name));
+            }
+        }
+        None
+    }
+}
+
 /// NoExpand indicates literal string replacement.
 ///
 /// It can be used with `replace` and `replace_all` to do a literal
@@ -744,6 +2798,183 @@ impl<'r, 't> Iterator for RegexSplits<'r, 't> {
     }
 }
 
+/// A piece of text yielded by `Regex::partition_text`.
+///
+/// `'t` is the lifetime of the text being partitioned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextSegment<'t> {
+    /// Text that was matched by the regular expression.
+    Matched(&'t str),
+    /// Non-empty text between two matches (or before the first match or
+    /// after the last one).
+    Unmatched(&'t str),
+}
+
+/// Yields every matched and unmatched segment of a string.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being partitioned.
+pub struct TextSegments<'r, 't> {
+    finder: FindMatches<'r, 't>,
+    last: usize,
+    next_match: Option<(usize, usize)>,
+}
+
+impl<'r, 't> Iterator for TextSegments<'r, 't> {
+    type Item = TextSegment<'t>;
+
+    fn next(&mut self) -> Option<TextSegment<'t>> {
+        let text = self.finder.text();
+        let (s, e) = match self.next_match.take() {
+            Some(m) => m,
+            None => match self.finder.next() {
+                Some(m) => m,
+                None => {
+                    if self.last >= text.len() {
+                        return None;
+                    }
+                    let unmatched = &text[self.last..];
+                    self.last = text.len();
+                    return Some(TextSegment::Unmatched(unmatched));
+                }
+            },
+        };
+        if self.last < s {
+            // Yield the text before the match first.
+            let unmatched = &text[self.last..s];
+            self.last = s;
+            self.next_match = Some((s, e));
+            return Some(TextSegment::Unmatched(unmatched));
+        }
+        self.last = e;
+        Some(TextSegment::Matched(&text[s..e]))
+    }
+}
+
+/// A token yielded by `Regex::tokenize`.
+///
+/// `'t` is the lifetime of the text being tokenized.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Token<'t> {
+    /// Text that was matched by the regular expression, along with the start
+    /// and end byte indices of the match.
+    Matched(&'t str, (usize, usize)),
+    /// Non-empty text between two matches (or before the first match or
+    /// after the last one).
+    Unmatched(&'t str),
+}
+
+/// Yields every matched and unmatched token of a string.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being tokenized.
+pub struct Tokens<'r, 't> {
+    segments: TextSegments<'r, 't>,
+    pos: usize,
+}
+
+impl<'r, 't> Iterator for Tokens<'r, 't> {
+    type Item = Token<'t>;
+
+    fn next(&mut self) -> Option<Token<'t>> {
+        let start = self.pos;
+        match self.segments.next() {
+            None => None,
+            Some(TextSegment::Matched(s)) => {
+                self.pos += s.len();
+                Some(Token::Matched(s, (start, self.pos)))
+            }
+            Some(TextSegment::Unmatched(s)) => {
+                self.pos += s.len();
+                Some(Token::Unmatched(s))
+            }
+        }
+    }
+}
+
+/// Yields every character position of a string along with the match that
+/// starts there, if any.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being searched.
+pub struct MatchPositions<'r, 't> {
+    re: &'r Regex,
+    text: &'t str,
+    pos: usize,
+    /// The leftmost-first match starting at or after `pos`, if it has been
+    /// searched for.
+    next_match: Option<(usize, usize)>,
+    /// Set when there are no more matches in the rest of the text.
+    done: bool,
+}
+
+impl<'r, 't> Iterator for MatchPositions<'r, 't> {
+    type Item = (usize, Option<(usize, usize)>);
+
+    fn next(&mut self) -> Option<(usize, Option<(usize, usize)>)> {
+        let pos = self.pos;
+        if pos >= self.text.len() {
+            return None;
+        }
+        let stale = match self.next_match {
+            None => true,
+            Some((s, _)) => s < pos,
+        };
+        if stale && !self.done {
+            self.next_match = self.re.find_at(self.text, pos);
+            self.done = self.next_match.is_none();
+        }
+        self.pos += 1;
+        while !is_char_boundary(self.text, self.pos) {
+            self.pos += 1;
+        }
+        match self.next_match {
+            Some((s, e)) if s == pos => Some((pos, Some((s, e)))),
+            _ => Some((pos, None)),
+        }
+    }
+}
+
+/// Yields the leftmost-first match in each window of a string.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being searched.
+pub struct MatchWindows<'r, 't> {
+    re: &'r Regex,
+    text: &'t str,
+    window: usize,
+    stride: usize,
+    next_start: Option<usize>,
+}
+
+impl<'r, 't> Iterator for MatchWindows<'r, 't> {
+    type Item = Option<(usize, usize)>;
+
+    fn next(&mut self) -> Option<Option<(usize, usize)>> {
+        let text = self.text;
+        let start = match self.next_start {
+            None => return None,
+            Some(start) => start,
+        };
+        let end = start.saturating_add(self.window);
+        if end >= text.len() {
+            self.next_start = None;
+        } else {
+            self.next_start = Some(start + self.stride);
+        }
+
+        let mut s = start;
+        while !is_char_boundary(text, s) {
+            s += 1;
+        }
+        let mut e = ::std::cmp::min(end, text.len());
+        while !is_char_boundary(text, e) {
+            e -= 1;
+        }
+        Some(self.re.find_between_positions(text, s, ::std::cmp::max(s, e)))
+    }
+}
+
 /// Yields at most `N` substrings delimited by a regular expression match.
 ///
 /// The last substring will be whatever remains after splitting.
@@ -772,6 +3003,177 @@ impl<'r, 't> Iterator for RegexSplitsN<'r, 't> {
     }
 }
 
+/// An iterator over the lines of a string, numbered from 1.
+struct NumberedLines<'t> {
+    text: &'t str,
+    line: usize,
+}
+
+impl<'t> NumberedLines<'t> {
+    fn new(text: &'t str) -> NumberedLines<'t> {
+        NumberedLines { text: text, line: 0 }
+    }
+}
+
+impl<'t> Iterator for NumberedLines<'t> {
+    type Item = (usize, &'t str);
+
+    fn next(&mut self) -> Option<(usize, &'t str)> {
+        if self.text.is_empty() {
+            return None;
+        }
+        let line = match self.text.find('\n') {
+            None => {
+                let line = self.text;
+                self.text = "";
+                line
+            }
+            Some(i) => {
+                let line = &self.text[..i];
+                self.text = &self.text[i + 1..];
+                line
+            }
+        };
+        self.line += 1;
+        Some((self.line, line))
+    }
+}
+
+/// An iterator over the lines of a string that contain a match, yielding
+/// each line with its line number.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being searched.
+pub struct GrepLines<'r, 't> {
+    re: &'r Regex,
+    lines: NumberedLines<'t>,
+}
+
+impl<'r, 't> Iterator for GrepLines<'r, 't> {
+    type Item = (usize, &'t str);
+
+    fn next(&mut self) -> Option<(usize, &'t str)> {
+        let re = self.re;
+        self.lines.find(|&(_, line)| re.is_match(line))
+    }
+}
+
+/// An iterator over the lines of a string that contain a match, yielding
+/// each line with its line number and the position of its first match.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being searched.
+pub struct GrepMatches<'r, 't> {
+    re: &'r Regex,
+    lines: NumberedLines<'t>,
+}
+
+impl<'r, 't> Iterator for GrepMatches<'r, 't> {
+    type Item = (usize, &'t str, (usize, usize));
+
+    fn next(&mut self) -> Option<(usize, &'t str, (usize, usize))> {
+        for (n, line) in &mut self.lines {
+            if let Some(pos) = self.re.find(line) {
+                return Some((n, line, pos));
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over the lines of a string that contain a match, yielding
+/// each line with its line number and the capture groups of its first match.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being searched.
+pub struct GrepCaptures<'r, 't> {
+    re: &'r Regex,
+    lines: NumberedLines<'t>,
+}
+
+impl<'r, 't> Iterator for GrepCaptures<'r, 't> {
+    type Item = (usize, &'t str, Captures<'t>);
+
+    fn next(&mut self) -> Option<(usize, &'t str, Captures<'t>)> {
+        for (n, line) in &mut self.lines {
+            if let Some(caps) = self.re.captures(line) {
+                return Some((n, line, caps));
+            }
+        }
+        None
+    }
+}
+
+/// An iterator over the lines of a string that don't contain a match,
+/// yielding each line with its line number.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the string being searched.
+pub struct GrepInverseLines<'r, 't> {
+    re: &'r Regex,
+    lines: NumberedLines<'t>,
+}
+
+impl<'r, 't> Iterator for GrepInverseLines<'r, 't> {
+    type Item = (usize, &'t str);
+
+    fn next(&mut self) -> Option<(usize, &'t str)> {
+        let re = self.re;
+        self.lines.find(|&(_, line)| !re.is_match(line))
+    }
+}
+
+/// A matching line or an error yielded by `Regex::grep_files`.
+///
+/// `'f` is the lifetime of the file paths.
+#[derive(Debug)]
+pub enum GrepFileItem<'f, P: 'f> {
+    /// A line that contains a match, with the path of its file, its line
+    /// number, the line and the position of its first match in the line.
+    Match(&'f P, usize, String, (usize, usize)),
+    /// A file that couldn't be read, with the error that occurred.
+    GrepError(&'f P, io::Error),
+}
+
+/// An iterator over the lines that contain a match in a list of files.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'f` is the
+/// lifetime of the file paths.
+pub struct GrepFiles<'r, 'f, P: 'f> {
+    re: &'r Regex,
+    files: slice::Iter<'f, P>,
+    pending: vec::IntoIter<GrepFileItem<'f, P>>,
+}
+
+impl<'r, 'f, P: AsRef<Path>> Iterator for GrepFiles<'r, 'f, P> {
+    type Item = GrepFileItem<'f, P>;
+
+    fn next(&mut self) -> Option<GrepFileItem<'f, P>> {
+        loop {
+            if let Some(item) = self.pending.next() {
+                return Some(item);
+            }
+            let path = match self.files.next() {
+                None => return None,
+                Some(path) => path,
+            };
+            let mut text = String::new();
+            let read = File::open(path).and_then(|mut f| {
+                f.read_to_string(&mut text)
+            });
+            if let Err(err) = read {
+                return Some(GrepFileItem::GrepError(path, err));
+            }
+            self.pending = self.re.grep_with_match(&text)
+                .map(|(n, line, pos)| {
+                    GrepFileItem::Match(path, n, line.to_owned(), pos)
+                })
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
 enum NamedGroups {
     Plugin(&'static [(&'static str, usize)]),
     Dynamic(Arc<HashMap<String, usize>>),
@@ -942,6 +3344,16 @@ impl<'t> Captures<'t> {
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Converts captures found in a window of `text` starting at `offset` to
+    /// captures of all of `text`.
+    fn offset_into(self, text: &'t str, offset: usize) -> Captures<'t> {
+        Captures {
+            text: text,
+            slots: self.slots.iter().map(|s| s.map(|i| offset + i)).collect(),
+            named_groups: self.named_groups,
+        }
+    }
 }
 
 impl<'t> fmt::Debug for Captures<'t> {
@@ -1077,6 +3489,87 @@ impl<'c> Iterator for SubCapturesNamed<'c> {
     }
 }
 
+/// An iterator over all non-overlapping matches in a memory mapped file.
+///
+/// The iterator yields a tuple of integers corresponding to the start and end
+/// of the match. The indices are byte offsets.
+///
+/// `'t` is the lifetime of the mapped bytes.
+pub struct MmapMatches<'t> {
+    exec: Arc<Exec>,
+    text: &'t [u8],
+    last_end: usize,
+    last_match: Option<usize>,
+}
+
+impl<'t> Iterator for MmapMatches<'t> {
+    type Item = (usize, usize);
+
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let searcher = self.exec.searcher();
+        loop {
+            if self.last_end > self.text.len() {
+                return None;
+            }
+            let (s, e) = match searcher.find_at(self.text, self.last_end) {
+                None => return None,
+                Some((s, e)) => (s, e),
+            };
+            // Don't accept empty matches immediately following a match.
+            if e == s && Some(self.last_end) == self.last_match {
+                if self.last_end >= self.text.len() {
+                    return None;
+                }
+                self.last_end = searcher.next_after_empty(
+                    self.text, self.last_end);
+                continue;
+            }
+            self.last_end = e;
+            self.last_match = Some(e);
+            return Some((s, e));
+        }
+    }
+}
+
+/// A parallel iterator over the text matched by one capture group in every
+/// successive non-overlapping match in a string.
+///
+/// This is only available when the `rayon` Cargo feature is enabled.
+///
+/// `'r` is the lifetime of the compiled regular expression and `'t` is the
+/// lifetime of the matched string.
+#[cfg(feature = "rayon")]
+pub struct ParallelGroups<'r, 't> {
+    re: &'r Regex,
+    text: &'t str,
+    group: usize,
+    starts: Vec<usize>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'r, 't> ::rayon::iter::ParallelIterator for ParallelGroups<'r, 't> {
+    type Item = Option<&'t str>;
+
+    fn drive_unindexed<C>(self, consumer: C) -> C::Result
+            where C: ::rayon::iter::plumbing::UnindexedConsumer<Self::Item> {
+        use rayon::prelude::*;
+
+        let (re, text, group) = (self.re, self.text, self.group);
+        self.starts.into_par_iter().map(move |start| {
+            let mut slots = vec![None; 2 * re.captures_len()];
+            re.read_captures_at(&mut slots, text, start);
+            match (slots.get(group * 2), slots.get(group * 2 + 1)) {
+                (Some(&Some(s)), Some(&Some(e))) => Some(&text[s..e]),
+                _ => None,
+            }
+        }).drive_unindexed(consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.starts.len())
+    }
+}
+
 /// An iterator that yields all non-overlapping capture groups matching a
 /// particular regular expression.
 ///
@@ -20,6 +20,7 @@ use Error;
 macro_rules! define_set {
     (
         $ty:ident,
+        $ty_builder:ident,
         $ty_set_matches:ident,
         $ty_set_matches_iter:ident,
         $ty_set_matches_into_iter:ident,
@@ -130,8 +131,31 @@ impl $ty {
     /// ```
     pub fn new<I, S>(exprs: I) -> Result<$ty, Error>
             where S: AsRef<str>, I: IntoIterator<Item=S> {
-        let exec = try!($exec_build(exprs));
-        Ok($ty(exec))
+        $ty::with_size_limit(10 * (1 << 20), exprs)
+    }
+
+    /// Create a new regex set with the given regular expressions and size
+    /// limit.
+    ///
+    /// The size limit is applied to the size of the *compiled* data
+    /// structure, which holds every regex in the set. If the data structure
+    /// exceeds the size given, then an error is returned.
+    ///
+    /// # Example
+    ///
+    /// A set that doesn't fit in the limit is rejected:
+    ///
+    /// ```rust
+    /// # use regex::{Error, RegexSet};
+    /// let res = RegexSet::with_size_limit(100, &[r"\w{50}", r"\d{50}"]);
+    /// match res {
+    ///     Err(Error::CompiledTooBig(100)) => {}
+    ///     _ => panic!("expected the size limit to be exceeded"),
+    /// }
+    /// ```
+    pub fn with_size_limit<I, S>(size: usize, exprs: I) -> Result<$ty, Error>
+            where S: AsRef<str>, I: IntoIterator<Item=S> {
+        $ty_builder::new(exprs).size_limit(size).build()
     }
 
     /// Returns true if and only if one of the regexes in this set matches
@@ -208,10 +232,185 @@ impl $ty {
         }
     }
 
+    /// Returns the sum of `weights[i]` for every regular expression `i` in
+    /// this set that matches in the given text.
+    ///
+    /// # Panics
+    ///
+    /// If the number of weights is not equal to `self.len()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::RegexSet;
+    /// let set = RegexSet::new(&[r"free", r"money", r"meeting"]).unwrap();
+    /// let weights = [0.5, 0.25, -1.0];
+    /// assert_eq!(set.weighted_score("free money", &weights), 0.75);
+    /// assert_eq!(set.weighted_score("free meeting", &weights), -0.5);
+    /// ```
+    pub fn weighted_score<W: AsRef<[f64]>>(
+        &self,
+        text: $text_ty,
+        weights: W,
+    ) -> f64 {
+        let weights = weights.as_ref();
+        assert_eq!(weights.len(), self.len(),
+                   "expected one weight for each regex in the set");
+        let mut matches = vec![false; self.0.regex_strings().len()];
+        self.0.searcher().many_matches_at(&mut matches, $as_bytes(text), 0);
+        matches.iter()
+               .zip(weights)
+               .filter(|&(&matched, _)| matched)
+               .fold(0.0, |score, (_, &weight)| score + weight)
+    }
+
+    /// Returns, for each regular expression in this set, the byte position
+    /// where its leftmost match in the given text starts, or `None` if it
+    /// doesn't match.
+    ///
+    /// The positions for all regular expressions are found in a single scan
+    /// of the text.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::RegexSet;
+    /// let set = RegexSet::new(&[r"[0-9]+", r"[a-z]+", r"!"]).unwrap();
+    /// assert_eq!(set.find_all_first_match_positions("AB cd 12"),
+    ///            vec![Some(6), Some(3), None]);
+    /// ```
+    pub fn find_all_first_match_positions(
+        &self,
+        text: $text_ty,
+    ) -> Vec<Option<usize>> {
+        self.0.first_match_starts($as_bytes(text))
+    }
+
+    /// Returns every match of every regular expression in this set,
+    /// including matches that overlap, as `(regex index, start, end)`.
+    ///
+    /// For each regular expression, this reports its leftmost-first match
+    /// starting at every position in the text where one starts. Matches are
+    /// grouped by regular expression and then ordered by where they start.
+    ///
+    /// Overlapping matches can't be found in a single scan of the text. Each
+    /// match reported needs its own search, so this can take time quadratic
+    /// in the length of the text. Prefer `matches` if you only need to know
+    /// which regular expressions matched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::RegexSet;
+    /// let set = RegexSet::new(&[r"aa", r"aaa"]).unwrap();
+    /// assert_eq!(set.overlapping_matches("aaaa"), vec![
+    ///     (0, 0, 2), (0, 1, 3), (0, 2, 4),
+    ///     (1, 0, 3), (1, 1, 4),
+    /// ]);
+    /// ```
+    pub fn overlapping_matches(
+        &self,
+        text: $text_ty,
+    ) -> Vec<(usize, usize, usize)> {
+        self.0.overlapping_matches($as_bytes(text))
+    }
+
     /// Returns the total number of regular expressions in this set.
     pub fn len(&self) -> usize {
         self.0.regex_strings().len()
     }
+
+    /// Returns true if this set contains no regular expressions.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the regular expressions in this set, in the order they were
+    /// given to the constructor.
+    ///
+    /// The index of a pattern in this slice is the index used for it by
+    /// `matches`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use regex::RegexSet;
+    /// let set = RegexSet::new(&[r"[0-9]+", r"[a-z]+"]).unwrap();
+    /// let matched: Vec<_> = set.matches("abc").into_iter()
+    ///     .map(|i| &set.patterns()[i])
+    ///     .collect();
+    /// assert_eq!(matched, vec!["[a-z]+"]);
+    /// ```
+    pub fn patterns(&self) -> &[String] {
+        self.0.regex_strings()
+    }
+}
+
+/// A configurable builder for a regex set.
+///
+/// The options set here are the defaults for every regular expression in
+/// the set. A regular expression can still override them with flags like
+/// `(?-m)`.
+///
+/// # Example
+///
+/// ```rust
+/// # use regex::RegexSetBuilder;
+/// let set = RegexSetBuilder::new(&[r"^foo$", r"^bar$"])
+///     .multi_line(true)
+///     .build()
+///     .unwrap();
+/// assert_eq!(set.matches("foo\nbar").into_iter().collect::<Vec<_>>(),
+///            vec![0, 1]);
+/// ```
+pub struct $ty_builder(ExecBuilder);
+
+impl $ty_builder {
+    /// Create a new builder for a regex set with the given regular
+    /// expressions.
+    pub fn new<I, S>(exprs: I) -> $ty_builder
+            where S: AsRef<str>, I: IntoIterator<Item=S> {
+        $ty_builder($exec_build(ExecBuilder::new_many(exprs)))
+    }
+
+    /// Sets the size limit of the compiled regex set.
+    ///
+    /// This is the same limit as the one given to `with_size_limit`. The
+    /// default is ~10MB.
+    pub fn size_limit(self, size: usize) -> $ty_builder {
+        $ty_builder(self.0.size_limit(size))
+    }
+
+    /// Sets whether `^` and `$` match at the beginning and end of lines
+    /// (the `m` flag) instead of only at the beginning and end of the text.
+    ///
+    /// It is disabled by default.
+    pub fn multi_line(self, yes: bool) -> $ty_builder {
+        $ty_builder(self.0.multi_line(yes))
+    }
+
+    /// Sets whether letters match both their upper and lower case forms
+    /// (the `i` flag).
+    ///
+    /// It is disabled by default.
+    pub fn case_insensitive(self, yes: bool) -> $ty_builder {
+        $ty_builder(self.0.case_insensitive(yes))
+    }
+
+    /// Sets whether `.` matches `\n` too (the `s` flag).
+    ///
+    /// It is disabled by default.
+    pub fn dot_matches_new_line(self, yes: bool) -> $ty_builder {
+        $ty_builder(self.0.dot_matches_new_line(yes))
+    }
+
+    /// Compiles the regex set.
+    ///
+    /// An error is returned if any of the regular expressions are invalid
+    /// or if the compiled set exceeds the size limit.
+    pub fn build(self) -> Result<$ty, Error> {
+        self.0.build().map($ty)
+    }
 }
 
 /// A set of matches returned by a regex set.
@@ -320,20 +519,22 @@ fn as_bytes_bytes(text: &[u8]) -> &[u8] { text }
 
 define_set! {
     RegexSet,
+    RegexSetBuilder,
     SetMatches,
     SetMatchesIter,
     SetMatchesIntoIter,
-    |exprs| ExecBuilder::new_many(exprs).build(),
+    |builder: ExecBuilder| builder,
     &str,
     as_bytes_str
 }
 
 define_set! {
     RegexSetBytes,
+    RegexSetBuilderBytes,
     SetMatchesBytes,
     SetMatchesIterBytes,
     SetMatchesIntoIterBytes,
-    |exprs| ExecBuilder::new_many(exprs).only_utf8(false).build(),
+    |builder: ExecBuilder| builder.only_utf8(false),
     &[u8],
     as_bytes_bytes
 }
@@ -38,6 +38,16 @@ impl SparseSet {
         }
     }
 
+    /// Empties the set and changes its capacity to `size`.
+    ///
+    /// The existing allocations are reused, so switching between programs
+    /// of different sizes doesn't allocate once the largest has been seen.
+    pub fn resize(&mut self, size: usize) {
+        self.dense.resize(size, 0);
+        self.sparse.resize(size, 0);
+        self.size = 0;
+    }
+
     pub fn len(&self) -> usize {
         self.size
     }
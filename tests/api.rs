@@ -69,6 +69,7 @@ fn empty_match_captures_iter() {
 fn capture_names() {
     let re = regex!(r"(.)(?P<a>.)");
     assert_eq!(3, re.captures_len());
+    assert_eq!(6, re.capture_slot_count());
     assert_eq!((3, Some(3)), re.capture_names().size_hint());
     assert_eq!(vec![None, None, Some("a")],
                re.capture_names().collect::<Vec<_>>());
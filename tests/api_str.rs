@@ -25,3 +25,876 @@ fn eq() {
     use regex::Regex;
     assert_eq!(regex!(r"[a-z]+"), Regex::new("[a-z]+").unwrap());
 }
+
+fn dfa_table_search(table: &::regex::DfaTable, text: &[u8]) -> Option<usize> {
+    let eoi = table.transitions[0].len() - 1;
+    let (mut s, mut last_match) = (1, None);
+    for (i, &b) in text.iter().enumerate() {
+        s = table.transitions[s][table.byte_classes[b as usize] as usize]
+            as usize;
+        if table.matches[s] {
+            last_match = Some(i);
+        }
+        if s == 0 {
+            break;
+        }
+    }
+    if s != 0 && table.matches[table.transitions[s][eoi] as usize] {
+        last_match = Some(text.len());
+    }
+    last_match
+}
+
+#[test]
+fn compile_to_dfa() {
+    let re = regex!(r"[a-z]+[0-9]");
+    let table = re.compile_to_dfa().unwrap();
+    assert_eq!(table.transitions.len(), table.matches.len());
+    assert!(!table.matches[0]);
+    for text in &["", "abc", "abc1", "12ab3cd4", "xyz!a9"] {
+        assert_eq!(re.find(text).map(|(_, e)| e),
+                   dfa_table_search(&table, text.as_bytes()));
+    }
+}
+
+#[test]
+fn compile_to_dfa_anchors() {
+    let re = regex!(r"^$|(?m)a$");
+    let table = re.compile_to_dfa().unwrap();
+    for text in &["", "a", "ba", "ab", "a\nb", "\n"] {
+        assert_eq!(re.find(text).map(|(_, e)| e),
+                   dfa_table_search(&table, text.as_bytes()));
+    }
+}
+
+#[test]
+fn compile_to_dfa_unicode_word_boundary() {
+    assert!(regex!(r"\bfoo\b").compile_to_dfa().is_err());
+    assert!(regex!(r"(?-u:\b)foo").compile_to_dfa().is_ok());
+}
+
+#[test]
+fn find_with_state() {
+    let re = regex!(r"[0-9]+");
+    let mut ms = vec![];
+    re.find_with_state("a1 b22 c333", &mut ms, |ms, m| ms.push(m))
+      .push((0, 0));
+    assert_eq!(ms, vec![(1, 2), (4, 6), (8, 11), (0, 0)]);
+}
+
+#[test]
+fn try_find_with_state() {
+    let re = regex!(r"[0-9]+");
+    let mut ms = vec![];
+    let res = re.try_find_with_state("1 22 333 4444", &mut ms, |ms, m| {
+        if m.1 - m.0 > 2 {
+            return Err(m);
+        }
+        ms.push(m);
+        Ok(())
+    });
+    assert_eq!(res, Err((5, 8)));
+    assert_eq!(ms, vec![(0, 1), (2, 4)]);
+
+    let res: Result<(), ()> =
+        re.try_find_with_state("1 22", &mut ms, |ms, m| Ok(ms.push(m)));
+    assert_eq!(res, Ok(()));
+    assert_eq!(ms.len(), 4);
+}
+
+#[test]
+fn chain() {
+    let re = regex!(r"(?i)(a)|b").chain(&regex!(r"(?x) (c) # comment"))
+                                 .unwrap();
+    assert_eq!(re.captures_len(), 3);
+    assert_eq!(findall!(re, "Ac bc aC"), vec![(0, 2), (3, 5)]);
+    let caps = re.captures("Ac").unwrap();
+    assert_eq!(caps.pos(1), Some((0, 1)));
+    assert_eq!(caps.pos(2), Some((1, 2)));
+
+    assert_eq!(findall!(regex!("").chain(&regex!("a")).unwrap(), "ba"),
+               vec![(1, 2)]);
+    assert!(regex!("(?P<x>a)").chain(&regex!("(?P<x>b)")).is_err());
+}
+
+#[test]
+fn alternate() {
+    let re = regex!(r"(a)").alternate(&regex!(r"(?P<b>b)|ab")).unwrap();
+    assert_eq!(re.captures_len(), 3);
+    assert_eq!(findall!(re, "ab b"), vec![(0, 1), (1, 2), (3, 4)]);
+    assert_eq!(re.captures("b").unwrap().name("b"), Some("b"));
+
+    let re = regex!("a").alternate(&regex!("")).unwrap();
+    assert_eq!(re.find("ba"), Some((0, 0)));
+    assert_eq!(re.find("ab"), Some((0, 1)));
+    let re = regex!("").alternate(&regex!("a")).unwrap();
+    assert_eq!(re.find("ab"), Some((0, 0)));
+}
+
+#[test]
+fn repeat() {
+    let re = regex!(r"(a|b)").repeat(2, Some(3)).unwrap();
+    assert_eq!(findall!(re, "a ab abbab"), vec![(2, 4), (5, 8), (8, 10)]);
+    assert_eq!(re.captures("ab").unwrap().pos(1), Some((1, 2)));
+
+    let re = regex!(r"a").repeat(2, None).unwrap();
+    assert_eq!(findall!(re, "a aa aaaaa"), vec![(2, 4), (5, 10)]);
+
+    assert!(regex!(r"a").repeat(3, Some(2)).is_err());
+    assert!(regex!(r"a{1000}").repeat(1000, None).is_err());
+}
+
+#[test]
+fn compose_keeps_size_limit() {
+    use regex::{Error, Regex};
+
+    let small = Regex::with_size_limit(2000, "a").unwrap();
+    assert!(regex!("a").repeat(100, None).is_ok());
+    match small.repeat(100, None) {
+        Err(Error::CompiledTooBig(2000)) => {}
+        r => panic!("expected CompiledTooBig(2000), got {:?}", r),
+    }
+    let long = regex!("a").repeat(100, None).unwrap();
+    match small.chain(&long) {
+        Err(Error::CompiledTooBig(2000)) => {}
+        r => panic!("expected CompiledTooBig(2000), got {:?}", r),
+    }
+    assert!(long.chain(&small).is_ok());
+}
+
+#[test]
+fn optional() {
+    let re = regex!(r"(a)b").optional().unwrap();
+    assert_eq!(re.find("ab"), Some((0, 2)));
+    assert_eq!(re.find("b"), Some((0, 0)));
+    assert_eq!(re.captures("ab").unwrap().pos(1), Some((0, 1)));
+    assert_eq!(regex!("").optional().unwrap().as_str(), "");
+}
+
+#[test]
+fn star() {
+    let re = regex!(r"(a|bc)").star().unwrap();
+    assert_eq!(findall!(re, "abca"), vec![(0, 4)]);
+    assert_eq!(findall!(re, "xa"), vec![(0, 0), (1, 2)]);
+    assert_eq!(re.captures("abca").unwrap().pos(1), Some((3, 4)));
+}
+
+#[test]
+fn plus() {
+    let re = regex!(r"(a|bc)").plus().unwrap();
+    assert_eq!(findall!(re, "abca"), vec![(0, 4)]);
+    assert_eq!(findall!(re, "xa b"), vec![(1, 2)]);
+    assert_eq!(re.captures("abca").unwrap().pos(1), Some((3, 4)));
+}
+
+#[test]
+fn with_capture_group() {
+    // Existing groups keep their indices and the new one comes last.
+    let (re, i) = regex!(r"(a)(?P<b>b)").with_capture_group(None).unwrap();
+    assert_eq!(i, 3);
+    assert_eq!(re.captures_len(), 4);
+    let caps = re.captures("xab").unwrap();
+    assert_eq!(caps.pos(1), Some((1, 2)));
+    assert_eq!(caps.pos(2), Some((2, 3)));
+    assert_eq!(caps.pos(3), Some((1, 3)));
+    assert_eq!(caps.name("b"), Some("b"));
+
+    // Wrapping again and composing keeps the indices too.
+    let (re, j) = re.with_capture_group(Some("all")).unwrap();
+    assert_eq!(j, 4);
+    let re = re.repeat(1, Some(2)).unwrap().simplify().unwrap();
+    let caps = re.captures("xabab").unwrap();
+    assert_eq!(caps.pos(1), Some((3, 4)));
+    assert_eq!(caps.pos(2), Some((4, 5)));
+    assert_eq!(caps.pos(3), Some((3, 5)));
+    assert_eq!(caps.pos(4), Some((3, 5)));
+    assert_eq!(caps.name("all"), Some("ab"));
+    let (re, k) = re.chain(&regex!(r"(c)")).unwrap()
+                    .with_capture_group(None).unwrap();
+    assert_eq!(k, 6);
+    let caps = re.captures("abc").unwrap();
+    assert_eq!(caps.pos(1), Some((0, 1)));
+    assert_eq!(caps.pos(4), Some((0, 2)));
+    assert_eq!(caps.pos(5), Some((2, 3)));
+    assert_eq!(caps.pos(6), Some((0, 3)));
+
+    // Once the groups are gone, the next one is group 1 again.
+    let re = re.without_captures().unwrap();
+    let (re, i) = re.with_capture_group(None).unwrap();
+    assert_eq!(i, 1);
+    assert_eq!(re.captures("abc").unwrap().pos(1), Some((0, 3)));
+
+    let (re, i) = regex!(r"a|b").with_capture_group(Some("ab")).unwrap();
+    assert_eq!(re.captures("b").unwrap().name("ab"), Some("b"));
+    assert_eq!(re.captures("b").unwrap().pos(i), Some((0, 1)));
+
+    assert!(regex!(r"(?P<a>a)").with_capture_group(Some("a")).is_err());
+    assert!(regex!(r"a").with_capture_group(Some("1a")).is_err());
+    assert!(regex!(r"").with_capture_group(None).is_err());
+}
+
+#[test]
+fn without_captures() {
+    let re = regex!(r"(?i)(a(?P<b>b))+|(c)*").without_captures().unwrap();
+    assert_eq!(re.captures_len(), 1);
+    assert_eq!(re.capture_names().count(), 1);
+    assert_eq!(findall!(re, "AbaB c"), vec![(0, 4), (5, 6)]);
+    assert_eq!(regex!("").without_captures().unwrap().as_str(), "");
+}
+
+#[test]
+fn with_prefix_literal() {
+    let re = regex!(r"(?i)(b)|c").with_prefix_literal("a.").unwrap();
+    assert_eq!(findall!(re, "a.B a.c axb A.b"), vec![(0, 3), (4, 7)]);
+    assert_eq!(re.captures("a.b").unwrap().pos(1), Some((2, 3)));
+    let re = regex!("").with_prefix_literal("a+").unwrap();
+    assert_eq!(findall!(re, "aa+"), vec![(1, 3)]);
+}
+
+#[test]
+fn with_suffix_literal() {
+    let re = regex!(r"(?i)a|(b)").with_suffix_literal("c*").unwrap();
+    assert_eq!(findall!(re, "Ac* bc* Bcc"), vec![(0, 3), (4, 7)]);
+    assert_eq!(re.captures("bc*").unwrap().pos(1), Some((0, 1)));
+    let re = regex!("").with_suffix_literal("c*").unwrap();
+    assert_eq!(findall!(re, "cc*"), vec![(1, 3)]);
+}
+
+#[test]
+fn is_deterministic() {
+    assert!(regex!(r"abc").is_deterministic());
+    assert!(regex!(r"(a|b)*c").is_deterministic());
+    assert!(regex!(r"[a-c]+d|x").is_deterministic());
+    assert!(!regex!(r"a*a").is_deterministic());
+    assert!(!regex!(r"[a-c]|b").is_deterministic());
+    assert!(!regex!(r"a?|b?").is_deterministic());
+}
+
+#[cfg(feature = "diagram")]
+#[test]
+fn to_automaton_diagram_svg() {
+    let svg = regex!(r"a(b|[<>])*").to_automaton_diagram_svg();
+    assert!(svg.starts_with("<svg "));
+    assert!(svg.ends_with("</svg>\n"));
+    assert!(svg.contains(">save 2<"));
+    assert!(svg.contains("stroke-dasharray"));
+}
+
+#[test]
+fn early_literal_match_disabled() {
+    use regex::internal::ExecBuilder;
+
+    for &early in &[true, false] {
+        let re = ExecBuilder::new("foo|bar")
+                             .early_literal_match(early)
+                             .build()
+                             .unwrap()
+                             .into_regex();
+        assert_eq!(findall!(re, "a foo bar"), vec![(2, 5), (6, 9)]);
+        let re = ExecBuilder::new("^foo")
+                             .early_literal_match(early)
+                             .build()
+                             .unwrap()
+                             .into_regex();
+        assert_eq!(findall!(re, "foo foo"), vec![(0, 3)]);
+    }
+}
+
+#[test]
+fn complexity_class() {
+    use regex::ComplexityClass::*;
+
+    assert_eq!(regex!(r"^abc").complexity_class(), O1);
+    assert_eq!(regex!(r"^(a|b)?c{2,5}$").complexity_class(), O1);
+    assert_eq!(regex!(r"abc").complexity_class(), On);
+    assert_eq!(regex!(r"^a*").complexity_class(), On);
+    assert_eq!(regex!(r"^(?:a|b+)c").complexity_class(), On);
+}
+
+#[test]
+fn group_names() {
+    let re = regex!(r"(?P<a>a)(b)(?P<c>c)");
+    assert_eq!(re.group_names().collect::<Vec<_>>(), vec![(1, "a"), (3, "c")]);
+    assert_eq!(regex!(r"(a)").group_names().count(), 0);
+}
+
+#[test]
+fn capture_group_by_name() {
+    let re = regex!(r"(?P<a>a)(b)(?P<c>c)");
+    assert_eq!(re.capture_group_by_name("a"), Some(1));
+    assert_eq!(re.capture_group_by_name("c"), Some(3));
+    assert_eq!(re.capture_group_by_name("b"), None);
+    assert_eq!(re.capture_group_by_name(""), None);
+}
+
+#[test]
+fn has_named_groups() {
+    assert!(regex!(r"(a)(?P<b>b)").has_named_groups());
+    assert!(!regex!(r"(a)(b)").has_named_groups());
+    assert!(!regex!(r"").has_named_groups());
+}
+
+#[test]
+fn find_first_named_group() {
+    let re = regex!(r"(?P<a>a)|(?P<b>b)");
+    assert_eq!(re.find_first_named_group("xbab", "a"), None);
+    assert_eq!(re.find_first_named_group("xbab", "b"), Some("b"));
+    assert_eq!(re.find_first_named_group("xbab", "c"), None);
+    assert_eq!(re.find_first_named_group("xyz", "a"), None);
+}
+
+#[cfg(feature = "rayon")]
+mod parallel {
+    extern crate rayon;
+    use self::rayon::prelude::*;
+
+    #[test]
+    fn find_all_groups_parallel() {
+        let re = regex!(r"(\w)(\d)?");
+        let text = "a1 b c3 ";
+        let got: Vec<_> = re.find_all_groups_parallel(text, 2).collect();
+        assert_eq!(got, vec![Some("1"), None, Some("3")]);
+        let got: Vec<_> = re.find_all_groups_parallel(text, 0).collect();
+        assert_eq!(got, vec![Some("a1"), Some("b"), Some("c3")]);
+        assert_eq!(re.find_all_groups_parallel(text, 3).count(), 3);
+    }
+}
+
+#[test]
+fn batch_find_all() {
+    let re = regex!(r"a*");
+    let got = re.batch_find_all(&["baab", "", "aa"]);
+    assert_eq!(got, vec![
+        findall!(re, "baab"),
+        findall!(re, ""),
+        findall!(re, "aa"),
+    ]);
+    assert!(re.batch_find_all(&[]).is_empty());
+}
+
+#[test]
+fn explain() {
+    // The test harness may compile regexes to byte instructions, so use the
+    // default compiler to get a predictable program.
+    use regex::Regex;
+    let re = Regex::new(r"(?P<x>a|[b-d_])+\b").unwrap();
+    assert_eq!(re.explain(), "\
+0: Starts capture group 0.
+1: Starts capture group 1 (x).
+2: Tries instruction 3 first and then instruction 4.
+3: Matches the literal character 'a', then goes to instruction 5.
+4: Matches any character '_' or from 'b' to 'd'.
+5: Ends capture group 1 (x).
+6: Tries instruction 1 first and then instruction 7.
+7: Matches a Unicode word boundary.
+8: Ends capture group 0.
+9: Reports a match.
+");
+}
+
+#[test]
+fn simplify() {
+    use regex::{Error, Regex};
+
+    let re = regex!(r"(?:(?:a?)+|(?:a?)+)b{1}");
+    assert_eq!(re.simplify().unwrap().as_str(), "(?u:a)*(?u:b)");
+    let simple = re.simplify().unwrap();
+    assert_eq!(findall!(simple, "b aab"), findall!(re, "b aab"));
+
+    // Capture groups are kept, so their repetitions can't be merged.
+    let re = regex!(r"(?:x|(a?)+)(?:y|y)");
+    assert_eq!(re.simplify().unwrap().as_str(), "(?:(?u:x)|((?u:a)?)+)(?u:y)");
+    assert_eq!(re.simplify().unwrap().captures_len(), 2);
+
+    // Non-greedy repetitions aren't merged with greedy ones.
+    let re = regex!(r"(?:a+)*?");
+    assert_eq!(re.simplify().unwrap().as_str(), "(?:(?u:a)+)*?");
+
+    // The simplified regex keeps the original's size limit.
+    let re = Regex::with_size_limit(2000, r"a{1}").unwrap();
+    let re = re.simplify().unwrap();
+    assert_eq!(re.as_str(), "(?u:a)");
+    match re.repeat(100, None) {
+        Err(Error::CompiledTooBig(2000)) => {}
+        r => panic!("expected CompiledTooBig(2000), got {:?}", r),
+    }
+}
+
+#[test]
+fn find_at_char() {
+    let re = regex!(r"\w+");
+    let text = "δύο λέξεις";
+    assert_eq!(re.find_at_char(text, 0), Some((0, 6)));
+    assert_eq!(re.find_at_char(text, 1), Some((2, 6)));
+    assert_eq!(re.find_at_char(text, 3), Some((7, 19)));
+    assert_eq!(re.find_at_char(text, 10), None);
+    assert_eq!(re.find_at_char(text, 11), None);
+
+    let re = regex!(r"$");
+    assert_eq!(re.find_at_char(text, 10), Some((19, 19)));
+}
+
+#[test]
+fn count_unique_matches() {
+    let re = regex!(r"a*");
+    assert_eq!(re.count_unique_matches("baabaab"), 2);
+    assert_eq!(re.count_unique_matches(""), 1);
+    assert_eq!(regex!(r"z").count_unique_matches("abc"), 0);
+}
+
+#[test]
+fn try_from_env() {
+    use std::env;
+    use regex::{Error, Regex, RegexEnvError};
+
+    env::set_var("REGEX_TEST_TRY_FROM_ENV_OK", r"[a-z]+");
+    let re = Regex::try_from_env("REGEX_TEST_TRY_FROM_ENV_OK").unwrap();
+    assert_eq!(re.find("12 ab"), Some((3, 5)));
+
+    env::set_var("REGEX_TEST_TRY_FROM_ENV_BAD", r"(");
+    match Regex::try_from_env("REGEX_TEST_TRY_FROM_ENV_BAD") {
+        Err(RegexEnvError::InvalidRegex(Error::Syntax(_))) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+
+    match Regex::try_from_env("REGEX_TEST_TRY_FROM_ENV_UNSET") {
+        Err(RegexEnvError::NotPresent(ref var)) => {
+            assert_eq!(var, "REGEX_TEST_TRY_FROM_ENV_UNSET");
+        }
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn pipe() {
+    let re = regex!(r"[0-9]");
+    assert_eq!(re.pipe("1ab23c4"), vec!["ab", "c"]);
+    assert_eq!(re.pipe("abc"), vec!["abc"]);
+    assert_eq!(re.pipe("123"), Vec::<&str>::new());
+    assert_eq!(re.pipe(""), Vec::<&str>::new());
+}
+
+#[test]
+fn partition_text() {
+    use regex::TextSegment::{Matched, Unmatched};
+
+    let re = regex!(r"[0-9]");
+    let got: Vec<_> = re.partition_text("12a3bc").collect();
+    assert_eq!(got, vec![
+        Matched("1"), Matched("2"), Unmatched("a"), Matched("3"),
+        Unmatched("bc"),
+    ]);
+    let got: Vec<_> = re.partition_text("abc").collect();
+    assert_eq!(got, vec![Unmatched("abc")]);
+    assert_eq!(re.partition_text("").count(), 0);
+
+    let re = regex!(r"a*");
+    let got: Vec<_> = re.partition_text("baab").collect();
+    assert_eq!(got, vec![
+        Matched(""), Unmatched("b"), Matched("aa"), Unmatched("b"),
+        Matched(""),
+    ]);
+}
+
+#[test]
+fn replace_nth() {
+    let re = regex!(r"([a-z])([0-9])");
+    assert_eq!(re.replace_nth("a1 b2 c3", 0, "$2$1"), "1a b2 c3");
+    assert_eq!(re.replace_nth("a1 b2 c3", 2, "$2$1"), "a1 b2 3c");
+    assert_eq!(re.replace_nth("a1 b2 c3", 3, "$2$1"), "a1 b2 c3");
+    assert_eq!(re.replace_nth("a1 b2 c3", 1, no_expand!("$1")), "a1 $1 c3");
+}
+
+#[test]
+fn find_between_positions() {
+    let re = regex!(r"\b[0-9]+\b");
+    let text = "12 345 6789";
+    assert_eq!(re.find_between_positions(text, 0, 11), Some((0, 2)));
+    assert_eq!(re.find_between_positions(text, 1, 11), Some((1, 2)));
+    assert_eq!(re.find_between_positions(text, 3, 5), Some((3, 5)));
+    assert_eq!(re.find_between_positions(text, 2, 3), None);
+    assert_eq!(re.find_between_positions(text, 11, 11), None);
+}
+
+#[test]
+fn find_all_between_positions() {
+    let re = regex!(r"[0-9]+");
+    let text = "12 345 6789";
+    assert_eq!(re.find_all_between_positions(text, 0, 11),
+               findall!(re, text));
+    assert_eq!(re.find_all_between_positions(text, 1, 9),
+               vec![(1, 2), (3, 6), (7, 9)]);
+    assert_eq!(re.find_all_between_positions(text, 2, 3), vec![]);
+}
+
+#[test]
+fn captures_between_positions() {
+    let re = regex!(r"(?P<l>[a-z])(?P<d>[0-9])?$");
+    let text = "a1 b2 c";
+    let caps = re.captures_between_positions(text, 0, 5).unwrap();
+    assert_eq!(caps.pos(0), Some((3, 5)));
+    assert_eq!(caps.name("l"), Some("b"));
+    assert_eq!(caps.pos(2), Some((4, 5)));
+    let caps = re.captures_between_positions(text, 0, 4).unwrap();
+    assert_eq!(caps.pos(0), Some((3, 4)));
+    assert_eq!(caps.name("d"), None);
+    assert!(re.captures_between_positions(text, 0, 3).is_none());
+}
+
+#[test]
+fn all_captures_between_positions() {
+    let re = regex!(r"(?P<l>[a-z])(?P<d>[0-9])?");
+    let text = "a1 b2 c";
+    let all = re.all_captures_between_positions(text, 1, 7);
+    let got: Vec<_> = all.iter()
+                         .map(|caps| (caps.pos(0), caps.name("d")))
+                         .collect();
+    assert_eq!(got, vec![(Some((3, 5)), Some("2")), (Some((6, 7)), None)]);
+    assert!(re.all_captures_between_positions(text, 1, 3).is_empty());
+}
+
+#[test]
+fn find_iter_windows() {
+    let re = regex!(r"[0-9]+");
+    let got: Vec<_> = re.find_iter_windows("1abc23", 2, 2).collect();
+    assert_eq!(got, vec![Some((0, 1)), None, Some((4, 6))]);
+    let got: Vec<_> = re.find_iter_windows("1ab234", 10, 1).collect();
+    assert_eq!(got, vec![Some((0, 1))]);
+    let got: Vec<_> = re.find_iter_windows("", 2, 2).collect();
+    assert_eq!(got, vec![None]);
+
+    // Window boundaries are moved out of the middle of characters.
+    let got: Vec<_> = re.find_iter_windows("1☃2☃3", 3, 2).collect();
+    assert_eq!(got, vec![Some((0, 1)), Some((4, 5)), Some((4, 5)),
+                         Some((8, 9))]);
+}
+
+#[test]
+fn matches_at_every_position() {
+    let re = regex!(r"a*");
+    assert_eq!(re.matches_at_every_position("baa"),
+               vec![Some((0, 0)), Some((1, 3)), Some((2, 3))]);
+    let re = regex!(r"\b\w");
+    assert_eq!(re.matches_at_every_position("☃a b"),
+               vec![None, None, None, Some((3, 4)), None, Some((5, 6))]);
+    assert_eq!(re.matches_at_every_position(""), vec![]);
+    assert_eq!(regex!(r"$").matches_at_every_position("ab"),
+               vec![None, None]);
+}
+
+#[test]
+fn max_thread_count() {
+    use regex::Regex;
+
+    // The literal prefix isn't found, so the NFA is never run.
+    assert_eq!(Regex::new(r"a").unwrap().max_thread_count("b"), 0);
+
+    let text = "aaaaaaaaaaaaaaaax";
+    let loose = Regex::new(r"\w*\w*\w*x").unwrap();
+    let tight = Regex::new(r"\w*x").unwrap();
+    let peak = loose.max_thread_count(text);
+    assert!(peak > tight.max_thread_count(text));
+    assert!(peak <= loose.explain().lines().count());
+}
+
+#[test]
+fn find_iter_with_positions() {
+    let re = regex!(r"\b\w");
+    let got: Vec<_> = re.find_iter_with_positions("☃a b").collect();
+    assert_eq!(got, vec![
+        (0, None), (3, Some((3, 4))), (4, None), (5, Some((5, 6))),
+    ]);
+    assert_eq!(regex!(r"$").find_iter_with_positions("ab").count(), 2);
+    assert_eq!(re.find_iter_with_positions("").count(), 0);
+}
+
+#[test]
+fn find_all_matching_predicate() {
+    let re = regex!(r"[a-z]+");
+    let text = "ab cde fg hij";
+    let got = re.find_all_matching_predicate(text, |(s, e)| e - s == 3);
+    assert_eq!(got, vec![(3, 6), (10, 13)]);
+    assert_eq!(re.find_all_matching_predicate(text, |_| true),
+               findall!(re, text));
+    assert_eq!(re.find_all_matching_predicate(text, |_| false), vec![]);
+}
+
+#[cfg(feature = "instrument")]
+#[test]
+fn benchmark_instruction_efficiency() {
+    use regex::Regex;
+
+    let text = "aaaaaaaaaaaaaaaax";
+    let loose = Regex::new(r"\w*\w*\w*x").unwrap();
+    let tight = Regex::new(r"\w*x").unwrap();
+    let loose = loose.benchmark_instruction_efficiency(text);
+    let tight = tight.benchmark_instruction_efficiency(text);
+    assert!(0.0 < loose && loose < tight);
+    let re = Regex::new(r"z").unwrap();
+    assert_eq!(re.benchmark_instruction_efficiency(text), 0.0);
+}
+
+#[test]
+fn to_rust_const_expr() {
+    let re = regex!(r"a+");
+    assert_eq!(re.to_rust_const_expr(), r##"Regex::new(r#"a+"#).unwrap()"##);
+
+    let re = regex!(r##"x"#y"##);
+    assert_eq!(re.to_rust_const_expr(),
+               r####"Regex::new(r##"x"#y"##).unwrap()"####);
+}
+
+#[test]
+fn find_all_with_fuel() {
+    let re = regex!(r"[0-9]+");
+    let text = text!("ab12cd345");
+    assert_eq!(re.find_all_with_fuel(text, 0), (vec![], 0));
+    assert_eq!(re.find_all_with_fuel(text, 4), (vec![(2, 4)], 0));
+    assert_eq!(re.find_all_with_fuel(text, 9), (vec![(2, 4), (6, 9)], 0));
+    assert_eq!(re.find_all_with_fuel(text, 10), (vec![(2, 4), (6, 9)], 1));
+
+    // Empty matches cost one unit each.
+    let re = regex!(r"");
+    assert_eq!(re.find_all_with_fuel(text!("ab"), 2),
+               (vec![(0, 0), (1, 1)], 0));
+    assert_eq!(re.find_all_with_fuel(text!("ab"), 5),
+               (vec![(0, 0), (1, 1), (2, 2)], 2));
+}
+
+#[test]
+fn exec_on_mmap() {
+    let re = regex!(r"\w+");
+    let found: Vec<_> = re.exec_on_mmap(b"ab \xFF\xFE cd").unwrap().collect();
+    assert_eq!(found, vec![(0, 2), (6, 8)]);
+
+    let re = regex!(r"");
+    let found: Vec<_> = re.exec_on_mmap(b"ab").unwrap().collect();
+    assert_eq!(found, vec![(0, 0), (1, 1), (2, 2)]);
+}
+
+#[test]
+fn exec_on_mmap_unicode() {
+    // Classes keep their Unicode meaning even though the haystack may not
+    // be valid UTF-8.
+    let re = regex!(r"\w+");
+    let found: Vec<_> = re.exec_on_mmap(b"caf\xC3\xA9 x").unwrap().collect();
+    assert_eq!(found, vec![(0, 5), (6, 7)]);
+    let found: Vec<_> =
+        re.exec_on_mmap(b"\xFF\xCE\xB1\xCE\xB2\xFFa").unwrap().collect();
+    assert_eq!(found, vec![(1, 5), (6, 7)]);
+
+    let re = regex!(r".");
+    let found: Vec<_> =
+        re.exec_on_mmap(b"\xE2\x98\x83\xFF").unwrap().collect();
+    assert_eq!(found, vec![(0, 3)]);
+
+    // A regex can still ask for bytes.
+    let re = regex!(r"(?-u:\w)+");
+    let found: Vec<_> = re.exec_on_mmap(b"caf\xC3\xA9 x").unwrap().collect();
+    assert_eq!(found, vec![(0, 3), (6, 7)]);
+}
+
+#[test]
+fn find_line_numbers() {
+    let re = regex!(r"[0-9]+");
+    let text = text!("1\na\n2 3\n\n4");
+    assert_eq!(re.find_line_numbers(text), vec![1, 3, 5]);
+    assert_eq!(re.find_line_numbers(text!("a\nb")), Vec::<usize>::new());
+
+    // Matches count for the line they start on.
+    let re = regex!(r"b\nc");
+    assert_eq!(re.find_line_numbers(text!("a\nb\nc")), vec![2]);
+}
+
+#[test]
+fn find_first_line() {
+    let re = regex!(r"[0-9]+");
+    assert_eq!(re.find_first_line(text!("a\nb12\n3")),
+               Some((2, "b12", (3, 5))));
+    assert_eq!(re.find_first_line(text!("42")), Some((1, "42", (0, 2))));
+    assert_eq!(re.find_first_line(text!("a\nb\n")), None);
+}
+
+#[test]
+fn grep() {
+    let re = regex!(r"^[0-9]+$");
+    let text = text!("12\nab\n\n3\n");
+    assert_eq!(re.grep(text).collect::<Vec<_>>(), vec![(1, "12"), (4, "3")]);
+    assert_eq!(re.grep(text!("")).count(), 0);
+
+    let re = regex!(r"^$");
+    assert_eq!(re.grep(text!("a\n\nb\n")).collect::<Vec<_>>(),
+               vec![(2, "")]);
+}
+
+#[test]
+fn grep_with_match() {
+    let re = regex!(r"b+");
+    let text = text!("abbcb\nccc\nb");
+    assert_eq!(re.grep_with_match(text).collect::<Vec<_>>(),
+               vec![(1, "abbcb", (1, 3)), (3, "b", (0, 1))]);
+}
+
+#[test]
+fn grep_captures() {
+    let re = regex!(r"(\w+)=(\w+)");
+    let text = text!("a=1 b=2\n--\nc=3");
+    let found: Vec<_> = re.grep_captures(text)
+        .map(|(n, line, caps)| (n, line, caps.at(1), caps.pos(2)))
+        .collect();
+    assert_eq!(found, vec![
+        (1, "a=1 b=2", Some("a"), Some((2, 3))),
+        (3, "c=3", Some("c"), Some((2, 3))),
+    ]);
+}
+
+#[test]
+fn grep_inverse() {
+    let re = regex!(r"[0-9]");
+    let text = text!("a\n1\n\nb2\nc");
+    assert_eq!(re.grep_inverse(text).collect::<Vec<_>>(),
+               vec![(1, "a"), (3, ""), (5, "c")]);
+}
+
+#[test]
+fn grep_count() {
+    let re = regex!(r"^[0-9]");
+    assert_eq!(re.grep_count(text!("1a\na1\n2\n\nb")), 2);
+    assert_eq!(re.grep_count(text!("")), 0);
+}
+
+#[test]
+fn grep_files() {
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use regex::GrepFileItem;
+
+    let dir = env::temp_dir();
+    let a = dir.join("regex-grep-files-a");
+    let b = dir.join("regex-grep-files-b");
+    let missing = dir.join("regex-grep-files-missing");
+    let _ = fs::remove_file(&missing);
+    File::create(&a).unwrap().write_all(b"x1\ny\nz22").unwrap();
+    File::create(&b).unwrap().write_all(b"nothing").unwrap();
+
+    let re = regex!(r"[0-9]+");
+    let files = vec![a.clone(), missing.clone(), b.clone()];
+    let mut found = vec![];
+    for item in re.grep_files(&files) {
+        match item {
+            GrepFileItem::Match(path, n, line, pos) => {
+                found.push((path.clone(), n, line, Some(pos)));
+            }
+            GrepFileItem::GrepError(path, _) => {
+                found.push((path.clone(), 0, String::new(), None));
+            }
+        }
+    }
+    fs::remove_file(&a).unwrap();
+    fs::remove_file(&b).unwrap();
+    assert_eq!(found, vec![
+        (a.clone(), 1, "x1".to_owned(), Some((1, 2))),
+        (a.clone(), 3, "z22".to_owned(), Some((1, 3))),
+        (missing, 0, String::new(), None),
+    ]);
+}
+
+#[test]
+fn highlight_matches() {
+    let re = regex!(r"[0-9]");
+    assert_eq!(re.highlight_matches(text!("a12b3"), "[", "]"), "a[12]b[3]");
+    assert_eq!(re.highlight_matches(text!("abc"), "[", "]"), "abc");
+    assert_eq!(re.highlight_matches(text!("7"), "[", "]"), "[7]");
+}
+
+#[test]
+fn highlight_groups() {
+    let re = regex!(r"((a)(b)?)(c)");
+    let tags = [(1, "<", ">"), (2, "[", "]"), (3, "{", "}"), (0, "(", ")")];
+    assert_eq!(re.highlight_groups(text!("xabc ac"), &tags),
+               "x(<[a]{b}>c) (<[a]>c)");
+
+    // Missing groups and empty groups.
+    let re = regex!(r"a(x*)");
+    assert_eq!(re.highlight_groups(text!("ab"), &[(1, "<", ">"), (5, "", "")]),
+               "a<>b");
+}
+
+#[test]
+fn tokenize() {
+    use regex::Token;
+
+    let re = regex!(r"[0-9]*");
+    let tokens: Vec<_> = re.tokenize(text!("1ab23")).collect();
+    assert_eq!(tokens, vec![
+        Token::Matched("1", (0, 1)),
+        Token::Unmatched("a"),
+        Token::Matched("", (2, 2)),
+        Token::Unmatched("b"),
+        Token::Matched("23", (3, 5)),
+    ]);
+    assert_eq!(re.tokenize(text!("")).collect::<Vec<_>>(),
+               vec![Token::Matched("", (0, 0))]);
+}
+
+#[test]
+fn find_with_step_limit() {
+    use regex::StepLimitExceeded;
+
+    let re = regex!(r"a+b");
+    assert_eq!(re.find_with_step_limit(text!("xaab"), 1000), Ok(Some((1, 4))));
+    assert_eq!(re.find_with_step_limit(text!("xaa"), 1000), Ok(None));
+
+    let text = ::std::iter::repeat("a").take(10000).collect::<String>();
+    assert_eq!(re.find_with_step_limit(&text, 1000),
+               Err(StepLimitExceeded(1000)));
+}
+
+#[test]
+fn find_at() {
+    let re = regex!(r"\b[0-9]+");
+    let text = text!("a12 34");
+    assert_eq!(re.find_at(text, 0), Some((4, 6)));
+    assert_eq!(re.find_at(text, 5), None);
+    assert_eq!(re.find_at(text, 6), None);
+
+    let re = regex!(r"^a");
+    assert_eq!(re.find_at(text!("aa"), 1), None);
+}
+
+#[test]
+#[should_panic]
+fn find_at_not_char_boundary() {
+    regex!(r"a").find_at(text!("☃a"), 1);
+}
+
+#[test]
+fn rfind() {
+    let re = regex!(r"a+");
+    assert_eq!(re.rfind(text!("aa b aaa")), Some((5, 8)));
+    assert_eq!(re.rfind(text!("b")), None);
+    // An empty match right after the last match isn't reported, just like
+    // with find_iter.
+    assert_eq!(regex!(r"b*").rfind(text!("ab")), Some((1, 2)));
+}
+
+#[test]
+fn find_anchored_at() {
+    let re = regex!(r"[a-z]+|\b[0-9]+");
+    let text = text!("ab 12x34");
+    assert_eq!(re.find_anchored_at(text, 0), Some((0, 2)));
+    assert_eq!(re.find_anchored_at(text, 1), Some((1, 2)));
+    assert_eq!(re.find_anchored_at(text, 2), None);
+    assert_eq!(re.find_anchored_at(text, 3), Some((3, 5)));
+    // \b still sees the character before the start position.
+    assert_eq!(re.find_anchored_at(text, 6), None);
+    assert_eq!(re.find_anchored_at(text, 8), None);
+    assert_eq!(regex!(r"x*").find_anchored_at(text, 8), Some((8, 8)));
+}
+
+#[test]
+#[should_panic]
+fn find_anchored_at_not_char_boundary() {
+    regex!(r"a").find_anchored_at(text!("☃a"), 1);
+}
@@ -38,3 +38,23 @@ mat!(dotstar_prefix_not_unicode, r"a", R(b"\xFFa"), Some((1, 2)));
 // Have fun with null bytes.
 mat!(null_bytes, r"(?P<cstr>[^\x00]+)\x00",
      R(b"foo\x00"), Some((0, 4)), Some((0, 3)));
+
+#[test]
+fn is_match_unicode_scalar() {
+    let re = regex!(r"(?-u:[\x80-\xFF]+)");
+    assert!(re.is_match_unicode_scalar(b"a\xCE\x93b\xE2\x98\x83"));
+    assert!(!re.is_match_unicode_scalar(b"a\xCE\x93b\xFF"));
+    assert!(!re.is_match_unicode_scalar(b"\xED\xA0\x80"));
+    assert!(!re.is_match_unicode_scalar(b"abc"));
+}
+
+#[test]
+fn captures_unicode_verified() {
+    let re = regex!(r"(?-u)(a)|(b[\x80-\xFF]*)");
+    let caps = re.captures_unicode_verified(b"xb\xCE\x93").unwrap();
+    assert_eq!(caps.pos(0), Some((1, 4)));
+    assert_eq!(caps.pos(1), None);
+    assert!(re.captures_unicode_verified(b"xb\xCE").is_none());
+    assert!(re.captures_unicode_verified(b"xb\xED\xA0\x80").is_none());
+    assert!(re.captures_unicode_verified(b"xyz").is_none());
+}
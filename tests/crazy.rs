@@ -37,6 +37,19 @@ matiter!(match_start_end_empty_rep, r"(?:^$)*", "a\nb\nc",
 matiter!(match_start_end_empty_rep_rev, r"(?:$^)*", "a\nb\nc",
          (0, 0), (1, 1), (2, 2), (3, 3), (4, 4), (5, 5));
 
+// Repeating expressions that can match without consuming anything must
+// match the empty string once per position and never spin.
+matiter!(match_empty1, r"(?:a*)*", "", (0, 0));
+matiter!(match_empty2, r"(?:a*)+", "", (0, 0));
+matiter!(match_empty3, r"(?:a*)+?", "", (0, 0));
+matiter!(match_empty4, r"(?:a?)*", "", (0, 0));
+matiter!(match_empty5, r"(?:a??)*", "", (0, 0));
+matiter!(match_empty6, r"(?:a*){2,5}", "", (0, 0));
+matiter!(match_empty7, r"(?:a*|b)*?", "", (0, 0));
+matiter!(match_empty8, r"(?:\b)*", "", (0, 0));
+matiter!(match_empty9, r"(?:a*)*", "bc", (0, 0), (1, 1), (2, 2));
+matiter!(match_empty10, r"(?:a?)+", "bc", (0, 0), (1, 1), (2, 2));
+
 // Test negated character classes.
 mat!(negclass_letters, r"[^ac]", "acx", Some((2, 3)));
 mat!(negclass_letter_comma, r"[^a,]", "a,x", Some((2, 3)));
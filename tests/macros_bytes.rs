@@ -14,6 +14,13 @@ macro_rules! no_expand {
     }}
 }
 
+macro_rules! regex_set_builder {
+    ($res:expr) => {{
+        use regex::bytes::RegexSetBuilder;
+        RegexSetBuilder::new($res)
+    }}
+}
+
 macro_rules! show {
     ($text:expr) => {{
         use std::ascii::escape_default;
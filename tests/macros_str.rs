@@ -14,6 +14,13 @@ macro_rules! no_expand {
     }}
 }
 
+macro_rules! regex_set_builder {
+    ($res:expr) => {{
+        use regex::RegexSetBuilder;
+        RegexSetBuilder::new($res)
+    }}
+}
+
 macro_rules! show { ($text:expr) => { $text } }
 
 // N.B. The expansion API for &str and &[u8] APIs differs slightly for now,
@@ -13,6 +13,18 @@ use regex::Regex;
 mat!(prefix_literal_match, r"^abc", r"abc", Some((0, 3)));
 mat!(prefix_literal_nomatch, r"^abc", r"zabc", None);
 mat!(one_literal_edge, r"abc", r"xxxxxab", None);
+mat!(first_byte_match, r"[a-p][0-9]+", r"zzzzzzzb12", Some((7, 10)));
+mat!(first_byte_nomatch, r"[a-p][0-9]+", r"zzzzzzzb", None);
+mat!(first_byte_unicode, r"[α-ω]x", r"zzαβxz", Some((4, 7)));
+mat!(first_byte_alternate, r"[a-f]1|[q-z]2", r"AAAz2", Some((3, 5)));
+mat!(leftmost_first_alternate, r"a|ab", r"ab", Some((0, 1)));
+mat!(leftmost_first_alternate_rev, r"ab|a", r"ab", Some((0, 2)));
+mat!(leftmost_first_alternate_caps, r"(a|ab)(c|bcd)", r"abcd",
+     Some((0, 4)), Some((0, 1)), Some((1, 4)));
+mat!(leftmost_first_greedy_star, r"a*", r"aaa", Some((0, 3)));
+mat!(leftmost_first_lazy_opt, r"a??", r"aaa", Some((0, 0)));
+mat!(leftmost_first_lazy_opt_alternate, r"(a??)(a|b)", r"ab",
+     Some((0, 1)), Some((0, 0)), Some((0, 1)));
 
 #[test]
 fn eq() {
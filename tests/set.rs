@@ -16,6 +16,9 @@ matset!(set15, &[r"\ba\b"], "hello a bye", 0);
 matset!(set16, &["a"], "a", 0);
 matset!(set17, &[".*a"], "a", 0);
 
+matset!(set18, &["(?m)^foo$", "^bar$"], "bar\nfoo\n", 0);
+matset!(set19, &["(?m)^foo$", "(?m)^bar$"], "bar\nfoo\n", 0, 1);
+
 nomatset!(nset1, &["a", "a"], "b");
 nomatset!(nset2, &["^foo", "bar$"], "bar foo");
 nomatset!(nset3, { let xs: &[&str] = &[]; xs }, "a");
@@ -28,3 +31,137 @@ fn regression_subsequent_matches() {
     assert!(set.matches(text).matched(1));
     assert!(set.matches(text).matched(1));
 }
+
+#[test]
+fn weighted_score() {
+    let set = regex_set!(&["a", "b", "ab"]);
+    assert_eq!(set.weighted_score(text!("xaby"), [1.0, 2.0, 4.0]), 7.0);
+    assert_eq!(set.weighted_score(text!("xba"), vec![1.0, 2.0, 4.0]), 3.0);
+    assert_eq!(set.weighted_score(text!("x"), &[1.0, 2.0, 4.0]), 0.0);
+}
+
+#[test]
+#[should_panic]
+fn weighted_score_wrong_len() {
+    let set = regex_set!(&["a", "b"]);
+    set.weighted_score(text!("a"), [1.0]);
+}
+
+#[test]
+fn find_all_first_match_positions() {
+    let set = regex_set!(&["a+b", "b", "^x", "c"]);
+    assert_eq!(set.find_all_first_match_positions(text!("zaaab b")),
+               vec![Some(1), Some(4), None, None]);
+
+    // A later ending match can start earlier.
+    let set = regex_set!(&["ab+c", "b"]);
+    assert_eq!(set.find_all_first_match_positions(text!("xabbc")),
+               vec![Some(1), Some(2)]);
+
+    let set = regex_set!(&["b+"]);
+    assert_eq!(set.find_all_first_match_positions(text!("abb")),
+               vec![Some(1)]);
+
+    let set = regex_set!({ let xs: &[&str] = &[]; xs });
+    assert_eq!(set.find_all_first_match_positions(text!("a")), vec![]);
+}
+
+#[test]
+fn len_is_empty_patterns() {
+    let set = regex_set!(&["a", "b+"]);
+    assert_eq!(set.len(), 2);
+    assert!(!set.is_empty());
+    assert_eq!(set.patterns(), &["a".to_owned(), "b+".to_owned()]);
+
+    let set = regex_set!({ let xs: &[&str] = &[]; xs });
+    assert_eq!(set.len(), 0);
+    assert!(set.is_empty());
+    assert!(set.patterns().is_empty());
+}
+
+#[test]
+fn multi_line() {
+    let text = text!("bar\nfoo\n");
+    let set = regex_set_builder!(&["^foo$", "(?-m)^foo$", "^bar"])
+        .multi_line(true)
+        .build()
+        .unwrap();
+    assert_eq!(set.matches(text).into_iter().collect::<Vec<_>>(), vec![0, 2]);
+
+    let set = regex_set_builder!(&["^foo$", "^bar"]).build().unwrap();
+    assert_eq!(set.matches(text).into_iter().collect::<Vec<_>>(), vec![1]);
+}
+
+#[test]
+fn case_insensitive() {
+    let set = regex_set_builder!(&["abc", "(?-i)abc", "x(?-i:Y)"])
+        .case_insensitive(true)
+        .build()
+        .unwrap();
+    assert_eq!(set.matches(text!("ABC xy")).into_iter().collect::<Vec<_>>(),
+               vec![0]);
+    assert_eq!(set.matches(text!("abc XY")).into_iter().collect::<Vec<_>>(),
+               vec![0, 1, 2]);
+}
+
+#[test]
+fn dot_matches_new_line() {
+    let set = regex_set_builder!(&["a.b", "(?-s)a.b"])
+        .dot_matches_new_line(true)
+        .build()
+        .unwrap();
+    assert_eq!(set.matches(text!("a\nb")).into_iter().collect::<Vec<_>>(),
+               vec![0]);
+    assert_eq!(set.matches(text!("a-b")).into_iter().collect::<Vec<_>>(),
+               vec![0, 1]);
+}
+
+#[test]
+fn concurrent_matches() {
+    use std::sync::Arc;
+    use std::thread;
+
+    let set = Arc::new(regex_set!(&["[0-9]+", "[a-z]+", "!"]));
+    let handles: Vec<_> = (0..8).map(|_| {
+        let set = set.clone();
+        thread::spawn(move || {
+            for _ in 0..100 {
+                let matches = set.matches(text!("abc 123"));
+                assert_eq!(matches.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+            }
+        })
+    }).collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+}
+
+#[test]
+fn size_limit() {
+    use regex::{Error, RegexSet};
+
+    let pats = &[r"\w{50}", r"\d{50}"];
+    match RegexSet::with_size_limit(100, pats) {
+        Err(Error::CompiledTooBig(100)) => {}
+        r => panic!("expected CompiledTooBig(100), got {:?}", r),
+    }
+    let set = RegexSet::with_size_limit(1 << 30, pats).unwrap();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn overlapping_matches() {
+    let set = regex_set!(&["aa", "aaa", "b"]);
+    assert_eq!(set.overlapping_matches(text!("aaaa")), vec![
+        (0, 0, 2), (0, 1, 3), (0, 2, 4),
+        (1, 0, 3), (1, 1, 4),
+    ]);
+
+    // Each match is the leftmost-first one for its starting position.
+    let set = regex_set!(&["a+", "a+?", "x*"]);
+    assert_eq!(set.overlapping_matches(text!("aab")), vec![
+        (0, 0, 2), (0, 1, 2),
+        (1, 0, 1), (1, 1, 2),
+        (2, 0, 0), (2, 1, 1), (2, 2, 2), (2, 3, 3),
+    ]);
+}
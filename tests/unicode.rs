@@ -10,6 +10,19 @@ mat!(uni_case_upper_nocase_flag, u!(r"(?i)\p{Lu}+"), "ΛΘΓΔα", Some((0, 10))
 mat!(uni_case_upper_nocase, u!(r"\p{L}+"), "ΛΘΓΔα", Some((0, 10)));
 mat!(uni_case_lower, u!(r"\p{Ll}+"), "ΛΘΓΔα", Some((8, 10)));
 
+// Case folds where more than two characters are equivalent or where the
+// mapping isn't symmetric.
+mat!(uni_case_sigma_upper, u!(r"(?i)σ"), "Σ", Some((0, 2)));
+mat!(uni_case_sigma_final, u!(r"(?i)σ"), "ς", Some((0, 2)));
+mat!(uni_case_final_sigma, u!(r"(?i)ς"), "Σσ", Some((0, 2)));
+mat!(uni_case_sigmas, u!(r"(?i)σ+"), "Σςσ", Some((0, 6)));
+mat!(uni_case_kelvin, u!(r"(?i)k"), "\u{212A}", Some((0, 3)));
+mat!(uni_case_kelvin_rev, u!(r"(?i)\x{212A}"), "K", Some((0, 1)));
+mat!(uni_case_long_s, u!(r"(?i)s"), "ſ", Some((0, 2)));
+mat!(uni_case_sharp_s, u!(r"(?i)ß"), "ẞ", Some((0, 3)));
+mat!(uni_case_sharp_s_rev, u!(r"(?i)ẞ"), "ß", Some((0, 2)));
+mat!(uni_case_class_sigma, u!(r"(?i)[σ]"), "ς", Some((0, 2)));
+
 // Test the Unicode friendliness of Perl character classes.
 mat!(uni_perl_w, u!(r"\w+"), "dδd", Some((0, 4)));
 mat!(uni_perl_w_not, u!(r"\w+"), "⥡", None);
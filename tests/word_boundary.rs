@@ -18,6 +18,13 @@ matiter!(wb15, r"\b(foo|bar|[A-Z])", "foo", (0, 3));
 matiter!(wb16, r"\b(foo|bar|[A-Z])\b", "X", (0, 1));
 matiter!(wb17, r"\b(foo|bar|[A-Z])\b", "XY");
 matiter!(wb18, r"\b(foo|bar|[A-Z])\b", "bar", (0, 3));
+
+// The edges of the text count as non-word characters.
+matiter!(wb_start, r"\bfoo", "foo", (0, 3));
+matiter!(wb_end, r"foo\b", "foo", (0, 3));
+matiter!(wb_not_start, r"\Bfoo", "foo");
+matiter!(wb_not_end, r"foo\B", "foo");
+matiter!(wb_not_start_inner, r"\Bfoo", "xfoo", (1, 4));
 matiter!(wb19, r"\b(foo|bar|[A-Z])\b", "foo", (0, 3));
 matiter!(wb20, r"\b(foo|bar|[A-Z])\b", "foo\n", (0, 3));
 matiter!(wb21, r"\b(foo|bar|[A-Z])\b", "ffoo bbar N x", (10, 11));
@@ -6,3 +6,8 @@ matiter!(ascii2, r"\Bx\B", "áxβ");
 // We can still get Unicode mode in byte regexes.
 matiter!(unicode1, r"(?u:\b)x(?u:\b)", "áxβ");
 matiter!(unicode2, r"(?u:\B)x(?u:\B)", "áxβ", (2, 3));
+
+// Non-ASCII characters next to the edges of the text are not word
+// characters.
+matiter!(ascii_not_inner, r"\Bfoo", "éfoo");
+matiter!(ascii_inner, r"\bfoo", "éfoo", (2, 5));
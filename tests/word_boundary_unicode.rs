@@ -5,3 +5,10 @@ matiter!(unicode2, r"\Bx\B", "áxβ", (2, 3));
 
 matiter!(ascii1, r"(?-u:\b)x(?-u:\b)", "áxβ", (2, 3));
 matiter!(ascii2, r"(?-u:\B)x(?-u:\B)", "áxβ");
+
+// Non-ASCII word characters next to the edges of the text.
+matiter!(unicode_start, r"\bé", "éa", (0, 2));
+matiter!(unicode_end, r"é\b", "aé", (1, 3));
+matiter!(unicode_not_start, r"\Bé", "é");
+matiter!(unicode_not_inner, r"\Bfoo", "éfoo", (2, 5));
+matiter!(unicode_inner, r"\bfoo", "éfoo");